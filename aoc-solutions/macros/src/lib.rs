@@ -0,0 +1,89 @@
+#![warn(clippy::pedantic)]
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Error, Expr, ExprLit, ItemFn, Lit, LitInt, Token, parse_macro_input};
+
+struct Args {
+    year: u16,
+    day: u8,
+    part: Option<u8>,
+    alternative: bool,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+        let mut numbers = Vec::new();
+        let mut alternative = false;
+        for arg in Punctuated::<Expr, Token![,]>::parse_terminated(input)? {
+            match arg {
+                Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) if !alternative => numbers.push(lit),
+                Expr::Path(path) if path.path.is_ident("alternative") => alternative = true,
+                arg => return Err(Error::new_spanned(arg, "expected `year, day[, part][, alternative]`")),
+            }
+        }
+        let (year, day, part) = match &numbers[..] {
+            [year, day] => (year, day, None),
+            [year, day, part] => (year, day, Some(part)),
+            _ => return Err(Error::new(span, "expected `year, day[, part][, alternative]`")),
+        };
+        let args = Args {
+            year: year.base10_parse()?,
+            day: day.base10_parse()?,
+            part: part.map(LitInt::base10_parse).transpose()?,
+            alternative,
+        };
+        if args.year < 2015 {
+            return Err(Error::new_spanned(year, format!("Invalid year: {}, Advent of Code started in 2015", args.year)));
+        }
+        if !(1..=25).contains(&args.day) {
+            return Err(Error::new_spanned(day, format!("Invalid day: {}, expected 1 to 25", args.day)));
+        }
+        if let (Some(part), Some(number)) = (part, args.part.filter(|part| ![1, 2].contains(part))) {
+            return Err(Error::new_spanned(part, format!("Invalid part number: {number}, expected 1 or 2")));
+        }
+        Ok(args)
+    }
+}
+
+/// Registers a function as a solution, see the `aoc` crate for details
+#[proc_macro_attribute]
+pub fn solution(args: TokenStream, item: TokenStream) -> TokenStream {
+    let Args { year, day, part, alternative } = parse_macro_input!(args as Args);
+    let function = parse_macro_input!(item as ItemFn);
+    let name = &function.sig.ident;
+    let (answers, part) = if let Some(part) = part {
+        (quote! { ::std::vec![(#part, #name(input).to_string())] }, quote! { ::std::option::Option::Some(#part) })
+    } else {
+        (quote! {
+            let (part1, part2) = #name(input);
+            ::std::vec![(1, part1.to_string()), (2, part2.to_string())]
+        }, quote! { ::std::option::Option::None })
+    };
+
+    quote! {
+        #function
+
+        const _: () = {
+            fn run(input: &str) -> ::std::vec::Vec<(u8, ::std::string::String)> {
+                #answers
+            }
+
+            ::aoc::__private::inventory::submit! {
+                ::aoc::__private::Solution {
+                    year: #year,
+                    day: #day,
+                    part: #part,
+                    alternative: #alternative,
+                    name: ::std::concat!(::std::module_path!(), "::", ::std::stringify!(#name)),
+                    file: ::std::file!(),
+                    line: ::std::line!(),
+                    run,
+                }
+            }
+        };
+    }.into()
+}
@@ -0,0 +1,110 @@
+#![warn(clippy::pedantic)]
+
+//! Write Advent of Code solutions in Rust and run them with the `aoc` CLI.
+//!
+//! Functions taking the input as a `&str` are registered with [`solution`],
+//! and the binary's `main` hands control to [`main`]:
+//! ```no_run
+//! use aoc::solution;
+//!
+//! #[solution(2023, 1, 1)]
+//! fn part_one(input: &str) -> usize {
+//!     input.lines().count()
+//! }
+//!
+//! // Omitting the part solves both, returning `(part1, part2)`
+//! #[solution(2023, 2)]
+//! fn both_parts(input: &str) -> (usize, usize) {
+//!     (input.len(), 0)
+//! }
+//!
+//! fn main() {
+//!     aoc::main();
+//! }
+//! ```
+//! `aoc run` builds any crate under the current directory that depends on this one,
+//! and runs its solutions alongside Python solutions.
+
+use std::env;
+use std::fmt::Write;
+use std::fs::write;
+use std::io::{Read, stdin};
+use std::process::exit;
+
+pub use aoc_solutions_macros::solution;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use inventory;
+
+    pub struct Solution {
+        pub year: u16,
+        pub day: u8,
+        pub part: Option<u8>,
+        pub alternative: bool,
+        pub name: &'static str,
+        pub file: &'static str,
+        pub line: u32,
+        pub run: fn(&str) -> Vec<(u8, String)>,
+    }
+
+    inventory::collect!(Solution);
+}
+
+use __private::Solution;
+
+/// Lists the registered solutions, one per line as `year, day, part (0 for both), alternative, line, file, name`
+fn list() {
+    for solution in inventory::iter::<Solution> {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            solution.year,
+            solution.day,
+            solution.part.unwrap_or(0),
+            solution.alternative,
+            solution.line,
+            solution.file,
+            solution.name,
+        );
+    }
+}
+
+/// Runs the solution called `name` with the input from stdin, writing its answers to `answers` as `part, answer` lines,
+/// so the solution can print whatever it likes
+fn run(name: &str, answers: &str) {
+    let Some(solution) = inventory::iter::<Solution>.into_iter().find(|solution| solution.name == name) else {
+        eprintln!("No solution called {name}");
+        exit(2);
+    };
+    let mut input = String::new();
+    if let Err(error) = stdin().read_to_string(&mut input) {
+        eprintln!("Failed to read input: {error}");
+        exit(2);
+    }
+    let mut answers_text = String::new();
+    for (part, answer) in (solution.run)(&input) {
+        writeln!(answers_text, "{part}\t{}", answer.replace('\n', " ")).unwrap();
+    }
+    if let Err(error) = write(answers, answers_text) {
+        eprintln!("Failed to write answers: {error}");
+        exit(2);
+    }
+}
+
+/// Embedded in every binary that calls [`main`], so the CLI can tell which binaries list solutions without running them
+#[doc(hidden)]
+pub const MARKER: &[u8] = b"aoc-solutions: lists solutions with `list`";
+
+/// Entry point for a binary containing solutions, it is run by the CLI rather than directly
+pub fn main() {
+    std::hint::black_box(MARKER);
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["list"] => list(),
+        ["run", name, answers] => run(name, answers),
+        _ => {
+            eprintln!("Solutions are run with `aoc run`");
+            exit(2);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
@@ -20,6 +20,62 @@ pub async fn get_input(config: &Config, year: u16, day: u8) -> Result<String> {
     Ok(resp)
 }
 
+/// What's extracted from a puzzle's page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub title: String,
+    /// The example inputs, in the order they appear
+    pub examples: Vec<String>,
+    /// The answer to the examples for each part that's unlocked, if one could be found
+    pub answers: Vec<Option<String>>,
+}
+
+impl Puzzle {
+    /// The number of parts that were unlocked when the puzzle was fetched
+    pub fn parts(&self) -> usize {
+        self.answers.len()
+    }
+}
+
+pub async fn get_puzzle(config: &Config, year: u16, day: u8) -> Result<Puzzle> {
+    let resp = reqwest::Client::new()
+        .get(url(year, day))
+        .header("Cookie", format!("session={}", config.token.as_ref().unwrap()))
+        .send().await?
+        .error_for_status()?
+        .text().await?;
+    parse_puzzle(&resp)
+}
+
+fn parse_puzzle(resp: &str) -> Result<Puzzle> {
+    let document = Html::parse_document(resp);
+    let articles: Vec<_> = document.select(&Selector::parse("article.day-desc").unwrap()).collect();
+    let examples = Selector::parse("pre > code").unwrap();
+    let answers = Selector::parse("code > em, em > code").unwrap();
+    let heading = articles.first()
+        .and_then(|article| article.select(&Selector::parse("h2").unwrap()).next())
+        .context("The puzzle's page has no description, it may not be unlocked yet")?
+        .text().collect::<String>();
+    // Headings look like `--- Day 1: Trebuchet?! ---`
+    let title = heading.trim_matches(|c: char| c == '-' || c.is_whitespace());
+    let title = title.split_once(": ").map_or(title, |(_, title)| title).to_string();
+    let examples = articles.iter()
+        .flat_map(|article| article.select(&examples))
+        .map(|example| example.text().collect())
+        .collect();
+    // Answers to examples are conventionally the last emphasised code in each part
+    let answers = articles.iter()
+        .map(|article| article.select(&answers)
+            .last()
+            .map(|answer| answer.text().collect()))
+        .collect();
+    Ok(Puzzle {
+        title,
+        examples,
+        answers,
+    })
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum SubmitResult {
     Accepted,
@@ -151,6 +207,14 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_parse_puzzle() {
+        let puzzle = parse_puzzle(include_str!("../test_data/puzzle.html")).unwrap();
+        assert_eq!(puzzle.title, "Trebuchet?!");
+        assert_eq!(puzzle.examples, ["1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\n", "two1nine\neightwothree\nabcone2threexyz\n"]);
+        assert_eq!(puzzle.answers, [Some("142".to_string()), Some("125".to_string())]);
+    }
+
     #[test]
     fn test_url() {
         assert_eq!(url(2023, 1), "https://adventofcode.com/2023/day/1");
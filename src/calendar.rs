@@ -0,0 +1,220 @@
+//! Working out which puzzle is meant when it isn't given in full
+//!
+//! Puzzles unlock at midnight EST, so dates are in EST rather than local time.
+
+use std::fmt::{Display, Formatter};
+use std::path::{Component, Path};
+use std::str::FromStr;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, FixedOffset, Duration, TimeZone, Utc};
+use crate::project::Settings;
+
+/// A year given on the command line, or a shortcut for a day
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum When {
+    Year(u16),
+    /// Today's puzzle, while an event is running
+    Today,
+    /// The most recently unlocked puzzle
+    Latest,
+    /// Relative to the default day, e.g. `+1` for the day after
+    Offset(i8),
+}
+
+impl FromStr for When {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "today" => Ok(Self::Today),
+            "latest" => Ok(Self::Latest),
+            _ if s.starts_with(['+', '-']) => s.parse().map(Self::Offset)
+                .map_err(|_| format!("Invalid offset: {s}, expected a number of days like `+1`")),
+            _ => s.parse().map(Self::Year)
+                .map_err(|_| format!("Invalid year: {s}, expected a year, `today`, `latest` or an offset like `+1`")),
+        }
+    }
+}
+
+impl Display for When {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Year(year) => write!(f, "{year}"),
+            Self::Today => write!(f, "today"),
+            Self::Latest => write!(f, "latest"),
+            Self::Offset(offset) => write!(f, "{offset:+}"),
+        }
+    }
+}
+
+fn est() -> FixedOffset {
+    FixedOffset::west_opt(5 * 60 * 60).unwrap()
+}
+
+pub fn now() -> DateTime<FixedOffset> {
+    Utc::now().with_timezone(&est())
+}
+
+/// When a puzzle unlocks
+pub fn unlocks_at(year: u16, day: u8) -> DateTime<FixedOffset> {
+    est().with_ymd_and_hms(year.into(), 12, day.into(), 0, 0, 0).unwrap()
+}
+
+/// The number of puzzles in an event, which was cut to 12 from 2025
+pub fn days_in(year: u16) -> u8 {
+    if year >= 2025 { 12 } else { 25 }
+}
+
+/// The year of the most recent event, which may not have started yet
+fn current_year() -> u16 {
+    u16::try_from(now().year()).unwrap()
+}
+
+/// Today's puzzle, failing if no event is running
+fn today() -> Result<(u16, u8)> {
+    let now = now();
+    let year = current_year();
+    let day = u8::try_from(now.day()).unwrap();
+    if now.month() != 12 || day > days_in(year) {
+        bail!("There's no puzzle today, the next event starts on December 1");
+    }
+    Ok((year, day))
+}
+
+/// The most recently unlocked puzzle
+fn latest() -> (u16, u8) {
+    let now = now();
+    let year = current_year();
+    if now.month() == 12 {
+        (year, u8::try_from(now.day()).unwrap().min(days_in(year)))
+    } else {
+        (year - 1, days_in(year - 1))
+    }
+}
+
+/// The puzzle to start, which is the next one if it unlocks within the hour, otherwise today's
+pub fn next_puzzle() -> Result<(u16, u8)> {
+    let soon = now() + Duration::hours(1);
+    let year = u16::try_from(soon.year()).unwrap();
+    let day = u8::try_from(soon.day()).unwrap();
+    if soon.month() == 12 && day <= days_in(year) && unlocks_at(year, day) > now() {
+        return Ok((year, day));
+    }
+    today()
+}
+
+/// Parses a year from a directory name like `2023` or `aoc2023`
+fn parse_year(name: &str) -> Option<u16> {
+    let digits = name.trim_start_matches(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '-');
+    digits.parse().ok().filter(|year| (2015..=current_year()).contains(year) && digits.len() == 4)
+}
+
+/// Parses a day from a name like `day05`, `d5` or `05`
+fn parse_day(name: &str) -> Option<u8> {
+    let name = name.to_ascii_lowercase();
+    let digits = name.strip_prefix("day").or_else(|| name.strip_prefix('d')).unwrap_or(&name);
+    let digits = digits.trim_start_matches(['_', '-']);
+    digits.parse().ok().filter(|day| (1..=25).contains(day) && digits.len() <= 2)
+}
+
+/// The year and day a path is for, from a layout like `2023/day05/` or `2023/day05.py`
+fn from_layout(path: &Path) -> (Option<u16>, Option<u8>) {
+    let mut year = None;
+    let mut day = None;
+    for component in path.components() {
+        let Component::Normal(name) = component else {
+            continue;
+        };
+        let path = Path::new(name);
+        // Only look at the stem, so `day05.py` is day 5
+        let name = path.file_stem().unwrap_or(name).to_string_lossy();
+        year = parse_year(&name).or(year);
+        day = parse_day(&name).or(day);
+    }
+    (year, day)
+}
+
+/// The puzzle assumed when it isn't given
+#[derive(Debug, Copy, Clone)]
+pub struct Defaults {
+    pub year: u16,
+    /// `None` if it can't be inferred
+    pub day: Option<u8>,
+}
+
+impl Defaults {
+    /// Infers the puzzle from the layout of `path` within `base_dir`, then the project settings, then the date
+    pub fn infer(base_dir: &Path, path: &Path, settings: &Settings) -> Self {
+        let (year, day) = from_layout(path.strip_prefix(base_dir).unwrap_or(path));
+        let (latest_year, latest_day) = latest();
+        let year = year.or(settings.year).unwrap_or(latest_year);
+        Self {
+            year,
+            day: day.or((year == latest_year).then_some(latest_day)),
+        }
+    }
+
+    /// Resolves a year or shortcut to a year, and a day for shortcuts
+    pub fn resolve(self, when: When) -> Result<(u16, Option<u8>)> {
+        match when {
+            When::Year(year) => {
+                if year < 2015 {
+                    bail!("Invalid year: {year}, Advent of Code started in 2015");
+                }
+                Ok((year, None))
+            }
+            When::Today => today().map(|(year, day)| (year, Some(day))),
+            When::Latest => Ok(latest()).map(|(year, day)| (year, Some(day))),
+            When::Offset(offset) => {
+                let day = self.day.with_context(|| format!("Couldn't work out which day `{when}` is relative to, give the year and day instead"))?;
+                let days = days_in(self.year);
+                match day.checked_add_signed(offset).filter(|day| (1..=days).contains(day)) {
+                    Some(day) => Ok((self.year, Some(day))),
+                    None => bail!("Day {day}{when} isn't in {}, which has days 1 to {days}", self.year),
+                }
+            }
+        }
+    }
+
+    /// Resolves the puzzles selected by a command's year, day and part arguments
+    ///
+    /// Shortcuts select a day, so the argument after them is the part, e.g. `today 2`.
+    pub fn select(self, when: Option<When>, day: Option<u8>, part: Option<u8>) -> Result<(Option<u16>, Option<u8>, Option<u8>)> {
+        let Some(when) = when else {
+            return Ok((None, day, part));
+        };
+        match self.resolve(when)? {
+            (year, None) => Ok((Some(year), day, part)),
+            (_, Some(_)) if part.is_some() => bail!("Only a part can be given after `{when}`"),
+            (year, Some(shortcut)) => Ok((Some(year), Some(shortcut), day)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_layout() {
+        assert_eq!(from_layout(Path::new("2023/day05")), (Some(2023), Some(5)));
+        assert_eq!(from_layout(Path::new("aoc2022/d7/solution.py")), (Some(2022), Some(7)));
+        assert_eq!(from_layout(Path::new("2021/day_12.py")), (Some(2021), Some(12)));
+        assert_eq!(from_layout(Path::new("src/utils")), (None, None));
+        assert_eq!(from_layout(Path::new("1999/day100")), (None, None));
+    }
+
+    #[test]
+    fn test_unlocks_at() {
+        assert_eq!(unlocks_at(2023, 1).with_timezone(&Utc).to_rfc3339(), "2023-12-01T05:00:00+00:00");
+    }
+
+    #[test]
+    fn test_offset() {
+        let defaults = Defaults { year: 2023, day: Some(5) };
+        assert_eq!(defaults.resolve(When::Offset(1)).unwrap(), (2023, Some(6)));
+        assert_eq!(defaults.resolve(When::Offset(-4)).unwrap(), (2023, Some(1)));
+        assert!(defaults.resolve(When::Offset(-5)).is_err());
+        assert!(Defaults { year: 2025, day: Some(12) }.resolve(When::Offset(1)).is_err());
+    }
+}
@@ -0,0 +1,23 @@
+use std::path::Path;
+use anyhow::Result;
+use crate::{Config, Scope};
+use crate::display::{ActionType, println};
+use crate::project::Settings;
+use crate::runner;
+
+pub fn doctor(config: &mut Config, base_dir: &Path, settings: &Settings, scope: Scope) -> Result<()> {
+    println("Trusted", ActionType::Info, format!("{} (to {scope})", base_dir.display()));
+    if config.strict_dirs.contains_key(base_dir) {
+        println("Strict", ActionType::Info, "only approved files are imported");
+    }
+    if config.token.is_some() {
+        println("Token", ActionType::Success, "set");
+    } else {
+        println("Token", ActionType::Warning, "not set, use `aoc token` to set it");
+    }
+    for runner in runner::runners(base_dir, settings, config.approvals(base_dir))? {
+        println("Runner", ActionType::Info, runner.name());
+        runner.describe()?;
+    }
+    Ok(())
+}
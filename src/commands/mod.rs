@@ -2,10 +2,20 @@ mod token;
 mod run;
 mod trust;
 mod new;
+mod start;
+mod submit;
 mod set_solution;
+mod doctor;
+mod watch;
+mod template;
 
 pub use token::token;
 pub use run::run;
-pub use trust::trust;
+pub use trust::{list_trusted, trust, untrust};
 pub use new::new;
+pub use start::start;
+pub use submit::submit;
 pub use set_solution::set_solution;
+pub use doctor::doctor;
+pub use watch::watch;
+pub use template::template;
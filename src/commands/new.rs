@@ -1,33 +1,121 @@
-use std::path::Path;
-use anyhow::{bail, Result};
-use tera::{Context, Tera};
+use std::env::current_dir;
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context as _, Result};
+use tera::Context;
 use tokio::fs;
-use tracing::info;
+use tracing::{info, warn};
+use crate::calendar::{Defaults, When};
+use crate::display::{ActionType, print_output, println};
+use crate::{api, Config, NewArgs, PartStatus};
+use crate::api::Puzzle;
+use crate::project::Settings;
+use crate::templates::{self, InputShape, Rendered, Template};
 
 fn or_ellipsis(opt: Option<impl ToString>) -> String {
     opt.map_or("...".to_string(), |val| val.to_string())
 }
 
-pub async fn new(base_dir: &Path, template: String, new_path: &Path, year: Option<u16>, day: Option<u8>, part: Option<u8>) -> Result<()> {
-    if new_path.exists() {
-        bail!("{} already exists!", new_path.display());
+/// The file, year, day and part arguments
+type Arguments = (Option<PathBuf>, Option<When>, Option<u8>, Option<u8>);
+
+/// `aoc new <template> 2023 5` and `aoc new <template> +1` leave out the file,
+/// in which case what was parsed as the file is the year or a shortcut, and the rest are shifted along
+fn shift(file: Option<PathBuf>, year: Option<When>, day: Option<u8>, part: Option<u8>) -> Result<Arguments> {
+    let Some(when) = file.as_ref().and_then(|file| file.to_str()?.parse::<When>().ok()) else {
+        return Ok((file, year, day, part));
+    };
+    if part.is_some() {
+        bail!("Too many arguments, expected a year, day and part after the template");
     }
-    let template_path = base_dir.join("templates")
-        .join(&template)
-        .with_extension("tera");
-    let file = fs::read_to_string(&template_path).await?;
+    let shifted = match year {
+        Some(When::Year(day)) => Some(u8::try_from(day).with_context(|| format!("Invalid day: {day}"))?),
+        Some(other) => bail!("Expected a day after `{when}`, got `{other}`"),
+        None => None,
+    };
+    Ok((None, Some(when), shifted, day))
+}
+
+/// Gets the puzzle's details and input, fetching and caching them if they haven't been already
+pub(super) async fn fetch_puzzle(config: &mut Config, year: u16, day: u8) -> Result<(Puzzle, String)> {
+    let solved = matches!(config.day(year, day).part1.status, PartStatus::Solved(_));
+    let puzzle = match config.day(year, day).puzzle.clone() {
+        // Part two is only on the page once part one is solved
+        Some(puzzle) if puzzle.parts() > 1 || !solved => puzzle,
+        _ => {
+            let puzzle = api::get_puzzle(config, year, day).await?;
+            config.day(year, day).puzzle = Some(puzzle.clone());
+            puzzle
+        }
+    };
+    let input = if let Some(input) = config.get_input(year, day) { input } else {
+        let input = api::get_input(config, year, day).await?;
+        config.day(year, day).input = Some(input.clone());
+        input
+    };
+    Ok((puzzle, input))
+}
+
+/// Adds the puzzle's details and input to the context
+async fn insert_puzzle(config: &mut Config, context: &mut Context, year: u16, day: u8) -> Result<()> {
+    let (puzzle, input) = fetch_puzzle(config, year, day).await?;
+    context.insert("title", &puzzle.title);
+    context.insert("examples", &puzzle.examples);
+    context.insert("answers", &puzzle.answers);
+    context.insert("input", &InputShape::detect(&input));
+    Ok(())
+}
+
+/// Creates a solution from a template, returning the paths of the files created
+pub async fn new(config: &mut Config, base_dir: &Path, settings: &Settings, args: NewArgs) -> Result<Vec<PathBuf>> {
+    let (file, when, day, part) = shift(args.file, args.year, args.day, args.part)?;
+    let cwd = current_dir()?;
+    let defaults = Defaults::infer(base_dir, &cwd.join(file.as_deref().unwrap_or(Path::new(""))), settings);
+    let (year, day, part) = defaults.select(when, day, part)?;
+    // The default day is only for the default year
+    let day = day.or(defaults.day.filter(|_| year.is_none_or(|year| year == defaults.year)));
+    let year = year.unwrap_or(defaults.year);
+    if file.is_none() && day.is_none() {
+        bail!("Couldn't work out which day to create a solution for, give the year and day");
+    }
+    let template = Template::find(base_dir, settings, &args.template)?;
     let mut context = Context::new();
-    context.insert("year", &or_ellipsis(year));
+    context.insert("template", &args.template);
+    context.insert("year", &year.to_string());
     context.insert("day", &or_ellipsis(day));
     context.insert("part", &or_ellipsis(part));
-    context.insert("url", &match (year, day, part) {
-        (Some(year), Some(day), Some(2)) => format!("https://adventofcode.com/{year}/day/{day}#part2"),
-        (Some(year), Some(day), _) => format!("https://adventofcode.com/{year}/day/{day}"),
-        (Some(year), _, _) => format!("https://adventofcode.com/{year}"),
-        (_, _, _) => "https://adventofcode.com".to_string(),
+    context.insert("url", &match (day, part) {
+        (Some(day), Some(2)) => format!("https://adventofcode.com/{year}/day/{day}#part2"),
+        (Some(day), _) => format!("https://adventofcode.com/{year}/day/{day}"),
+        (_, _) => format!("https://adventofcode.com/{year}"),
     });
-    let rendered = Tera::one_off(&file, &context, false)?;
-    fs::write(&new_path, rendered).await?;
-    info!("Successfully created {}!", new_path.display());
-    Ok(())
+    if let Some(day) = day {
+        if let Err(err) = insert_puzzle(config, &mut context, year, day).await {
+            warn!("Couldn't fetch the puzzle, so templates can't use its details: {err}");
+        }
+    }
+    let mut tera = templates::environment(base_dir, settings, config)?;
+    let (file, dir) = match file {
+        Some(file) => (file.clone(), file),
+        None => (base_dir.join(templates::render_path(&mut tera, settings.path(), &context)?), base_dir.to_path_buf()),
+    };
+    let rendered = template.render(&mut tera, &context, &file, &dir)?;
+    if let Some(existing) = rendered.iter().find(|rendered| rendered.path.exists()) {
+        bail!("{} already exists!", existing.path.display());
+    }
+    let mut created = Vec::new();
+    for Rendered { path, contents } in rendered {
+        let shown = path.strip_prefix(&cwd).unwrap_or(&path).display();
+        if args.dry_run {
+            println("Create", ActionType::Info, shown);
+            print_output(&contents);
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, contents).await?;
+        info!("Successfully created {shown}!");
+        created.push(path);
+    }
+    Ok(created)
 }
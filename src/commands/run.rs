@@ -1,20 +1,23 @@
-use std::fs::read_to_string;
+use std::env::current_dir;
+use std::fmt::Write;
+use std::fs::write;
+use std::path::Path;
 use std::sync::RwLock;
-use anyhow::Result;
+use std::time::Duration;
+use anyhow::{bail, Result};
 use futures::future::try_join_all;
-use glob::glob;
-use indicatif::ProgressIterator;
-use pyo3::{append_to_inittab, prepare_freethreaded_python, Python};
-use pyo3::types::PyModule;
-use tracing::{error, warn};
+use indicatif::ProgressBar;
+use tracing::{error, info, warn};
 use crate::{api, PartStatus};
 use crate::api::{SubmitResult, WrongAnswerReason};
-use crate::Config;
-use crate::display::{ActionType, confirm, FormatTraceBack, println, progress_bar};
-use crate::python::aoc;
-use crate::python::solutions::{Solution, SOLUTIONS};
+use crate::{Config, RunArgs};
+use crate::calendar::Defaults;
+use crate::display::{ActionType, confirm, print_output, println, progress_bar, suspend};
+use crate::project::{Settings, SubmitPolicy};
+use crate::runner;
+use crate::solutions::{self, Solution};
 
-async fn get_input<'s>(config: &Config, solution: &'s Solution, new_inputs: &RwLock<Vec<(u16, u8, String)>>) -> Result<(&'s Solution, String)> {
+pub(super) async fn get_input<'s>(config: &Config, solution: &'s Solution, new_inputs: &RwLock<Vec<(u16, u8, String)>>) -> Result<(&'s Solution, String)> {
     let input = if let Some(input) = config.get_input(solution.year, solution.day) { input } else {
         let input = api::get_input(config, solution.year, solution.day).await?;
         new_inputs.write().unwrap().push((solution.year, solution.day, input.clone()));
@@ -23,8 +26,34 @@ async fn get_input<'s>(config: &Config, solution: &'s Solution, new_inputs: &RwL
     Ok((solution, input))
 }
 
-async fn handle_submit(config: &mut Config, solution: &Solution, identifier: &String, result: &String) -> Result<()> {
-    let submit_result = api::submit(config, solution.year, solution.day, solution.part, result).await?;
+/// An answer to a single part
+pub(super) struct Answer {
+    year: u16,
+    day: u8,
+    part: u8,
+    identifier: String,
+    result: String,
+    /// Anything the solution printed, only set on the first answer from a solution
+    output: Option<String>,
+}
+
+impl Answer {
+    /// An answer given on the command line rather than by a solution
+    pub(super) fn manual(year: u16, day: u8, part: u8, result: String) -> Self {
+        Self {
+            year,
+            day,
+            part,
+            identifier: format!("{year} day {day} part {part}"),
+            result,
+            output: None,
+        }
+    }
+}
+
+async fn handle_submit(config: &mut Config, answer: &Answer) -> Result<()> {
+    let Answer { identifier, result, .. } = answer;
+    let submit_result = api::submit(config, answer.year, answer.day, answer.part, result).await?;
     match &submit_result.result {
         SubmitResult::Accepted => {
             println("Solved", ActionType::Success, identifier);
@@ -51,8 +80,9 @@ async fn handle_submit(config: &mut Config, solution: &Solution, identifier: &St
     Ok(())
 }
 
-async fn handle_result(config: &mut Config, solution: &Solution, identifier: &String, result: &String, submit: bool, disable_submit_safety: bool) -> Result<()> {
-    match &config.day(solution.year, solution.day).part(solution.part).status {
+pub(super) async fn handle_result(config: &mut Config, answer: &Answer, submit: SubmitPolicy, disable_submit_safety: bool) -> Result<()> {
+    let Answer { identifier, result, .. } = answer;
+    match &config.day(answer.year, answer.day).part(answer.part).status {
         PartStatus::Active { min, max, incorrect } => {
             if incorrect.contains(result) && !disable_submit_safety {
                 println("Incorrect", ActionType::Failure, format!("{identifier}: {result}, already tried"));
@@ -60,8 +90,8 @@ async fn handle_result(config: &mut Config, solution: &Solution, identifier: &St
                 println("Incorrect", ActionType::Failure, format!("{identifier}: {result}, must be greater than {}", min.unwrap()));
             } else if max.is_some() && !disable_submit_safety && result.parse::<i64>()? > max.unwrap() {
                 println("Incorrect", ActionType::Failure, format!("{identifier}: {result}, must be less than {}", max.unwrap()));
-            } else if submit && confirm(format!("Submit {result} for {identifier}?"))? {
-                handle_submit(config, solution, identifier, result).await?;
+            } else if submit == SubmitPolicy::Always || submit == SubmitPolicy::Ask && confirm(format!("Submit {result} for {identifier}?"))? {
+                handle_submit(config, answer).await?;
             } else {
                 println("Run", ActionType::Success, format!("{identifier}: {result}"));
             }
@@ -77,49 +107,121 @@ async fn handle_result(config: &mut Config, solution: &Solution, identifier: &St
     Ok(())
 }
 
-pub async fn run(config: &mut Config, year: Option<u16>, day: Option<u8>, part: Option<u8>, submit: bool, disable_submit_safety: bool) -> Result<()> {
-    append_to_inittab!(aoc);
-    prepare_freethreaded_python();
-    let files: Vec<_> = glob("./**/*.py")?.collect();
-    let mut import_failures = 0u16;
-    let bar = progress_bar("Importing".to_string(), ActionType::Prepare, files.len() as u64)?;
-    Python::with_gil(|py| -> Result<()> {
-        for (i, file) in files.into_iter().enumerate().progress_with(bar.clone()) {
-            let file = file?;
-            bar.set_message(file.display().to_string());
-            if let Err(error) = PyModule::from_code(py, &read_to_string(&file)?, &file.display().to_string(), &format!("aoc_{i}")).tb() {
-                error!("Failed to import {}\n\n{error}", file.display());
-                import_failures += 1;
+pub(super) fn select_solutions(year: Option<u16>, day: Option<u8>, part: Option<u8>) -> Vec<Solution> {
+    let mut solutions: Vec<_> = solutions::solutions().into_iter()
+        .filter(|solution| {
+            if let Some(year) = year {
+                if solution.year != year {
+                    return false;
+                }
             }
-        }
-        Ok(())
-    })?;
-
-    let new_inputs: RwLock<Vec<(u16, u8, String)>> = RwLock::new(Vec::new());
-    let solutions: Vec<_> = {
-        let solutions = SOLUTIONS.read().unwrap();
-        solutions.iter()
-            .filter(|solution| {
-                if let Some(year) = year {
-                    if solution.year != year {
-                        return false;
-                    }
+            if let Some(day) = day {
+                if solution.day != day {
+                    return false;
                 }
-                if let Some(day) = day {
-                    if solution.day != day {
-                        return false;
-                    }
+            }
+            if let Some(part) = part {
+                if !solution.parts().contains(&part) {
+                    return false;
                 }
-                if let Some(part) = part {
-                    if solution.part != part {
-                        return false;
-                    }
+            }
+            true
+        })
+        .collect();
+    // Keeps solutions for a day together so alternative implementations of a part can be cross-checked
+    solutions.sort_by_key(|solution| (solution.year, solution.day));
+    solutions
+}
+
+/// Runs a solution, returning its answers to the selected parts, or `None` if it failed
+///
+/// Anything the solution prints is captured and appended to `output_log`.
+///
+/// If `debug` is set, failures start a post-mortem debugger.
+pub(super) fn run_solution(solution: &Solution, input: &str, part: Option<u8>, bar: &ProgressBar, output_log: &mut String, debug: bool, timeout: Option<Duration>) -> Result<Option<Vec<Answer>>> {
+    let name = &solution.name;
+    bar.set_message(format!("{} day {} ({name})", solution.year, solution.day));
+    let (results, output) = solution.run(input, timeout)?;
+    bar.inc(1);
+    if !output.is_empty() {
+        writeln!(output_log, "==> {} day {} ({name}) <==\n{output}", solution.year, solution.day)?;
+    }
+    let results = match results {
+        Ok(results) => results,
+        Err(error) => {
+            let identifier = format!("{} day {} ({name})", solution.year, solution.day);
+            error!("{} day {} ({}): Failed to run solution:\n\n{}", solution.year, solution.day, solution.description(), error.message);
+            print_output(&output);
+            match error.debugger {
+                Some(debugger) if debug => {
+                    info!("Starting debugger for {identifier}, use `q` to quit it and continue");
+                    suspend(debugger)?;
                 }
-                true
-            })
-            .cloned()
-            .collect()
+                None if debug => warn!("{identifier} can't be debugged, the debugger can only be used with Python solutions"),
+                _ => {}
+            }
+            println("Skipped", ActionType::Warning, identifier);
+            return Ok(None);
+        }
     };
+    let mut answers: Vec<_> = results.into_iter()
+        .filter(|(solved, _)| part.is_none_or(|part| part == *solved))
+        .map(|(part, result)| Answer {
+            year: solution.year,
+            day: solution.day,
+            part,
+            identifier: format!("{} day {} part {part} ({name})", solution.year, solution.day),
+            result,
+            output: None,
+        })
+        .collect();
+    if let Some(answer) = answers.first_mut() {
+        answer.output = Some(output);
+    } else {
+        print_output(&output);
+    }
+    Ok(Some(answers))
+}
+
+/// Handles the answers from every solution for a day, cross-checking alternative implementations of each part,
+/// returning the number of parts whose implementations disagree
+///
+/// Answers are only submitted if `submit` isn't [`SubmitPolicy::Never`].
+pub(super) async fn report_answers(config: &mut Config, answers: &[Answer], submit: SubmitPolicy, disable_submit_safety: bool) -> Result<u16> {
+    let mut mismatches = 0;
+    for part in [1, 2] {
+        let implementations: Vec<_> = answers.iter().filter(|answer| answer.part == part).collect();
+        let agree = implementations.windows(2).all(|pair| pair[0].result == pair[1].result);
+        if !agree {
+            let answer = implementations[0];
+            println("Mismatch", ActionType::Failure, format!("{} day {} part {part}: implementations disagree", answer.year, answer.day));
+            mismatches += 1;
+        }
+        for (i, answer) in implementations.into_iter().enumerate() {
+            // Only the first implementation is submitted, and only when all of them agree
+            let submit = if agree && i == 0 { submit } else { SubmitPolicy::Never };
+            handle_result(config, answer, submit, disable_submit_safety).await?;
+            if let Some(output) = &answer.output {
+                print_output(output);
+            }
+        }
+    }
+    Ok(mismatches)
+}
+
+pub async fn run(config: &mut Config, base_dir: &Path, settings: &Settings, args: &RunArgs) -> Result<()> {
+    let RunArgs { year, day, part, submit, disable_submit_safety, ref log, pdb } = *args;
+    let (year, day, part) = Defaults::infer(base_dir, &current_dir()?, settings).select(year, day, part)?;
+    let submit = match (submit, settings.submit()) {
+        (true, SubmitPolicy::Never) => bail!("Submitting is disabled by the `submit` setting"),
+        (true, policy) => policy,
+        (false, _) => SubmitPolicy::Never,
+    };
+    let timeout = settings.timeout()?;
+    let runners = runner::runners(base_dir, settings, config.approvals(base_dir))?;
+    let import_failures = runner::load_solutions(&runners, settings.roots(base_dir).as_deref(), config.approvals(base_dir))?;
+    let solutions = select_solutions(year, day, part);
+    let new_inputs: RwLock<Vec<(u16, u8, String)>> = RwLock::new(Vec::new());
     let solutions = try_join_all(solutions.iter()
         .map(|solution| get_input(config, solution, &new_inputs))
     ).await?;
@@ -129,33 +231,24 @@ pub async fn run(config: &mut Config, year: Option<u16>, day: Option<u8>, part:
 
     let mut failures = 0u16;
     let mut skips = 0u16;
+    let mut mismatches = 0u16;
+    let mut output_log = String::new();
     let bar = progress_bar("Running".to_string(), ActionType::Progress, solutions.len() as u64)?;
-    for (solution, input) in &solutions {
-        let (identifier, result) = Python::with_gil(|py| -> Result<_> {
-            let identifier = format!("{} day {} part {} ({})", solution.year, solution.day, solution.part, solution.function.getattr(py, "__name__")?);
-            bar.set_message(identifier.clone());
-            let result = match solution.function.call1(py, (input, )).tb() {
-                Ok(result) => result,
-                Err(error) => {
-                    error!("{identifier}: Failed to run solution:\n\n{error}");
-                    failures += 1;
-                    return Ok((identifier, None));
-                }
-            };
-            let result: String = result.getattr(py, "__str__")?.call0(py)?.extract(py)?;
-            Ok((identifier, Some(result)))
-        })?;
-        let Some(result) = result else {
-            println(
-                "Skipped",
-                ActionType::Warning,
-                identifier,
-            );
-            skips += 1;
-            continue;
-        };
-        handle_result(config, solution, &identifier, &result, submit, disable_submit_safety).await?;
-        bar.inc(1);
+    for day_solutions in solutions.chunk_by(|(a, _), (b, _)| (a.year, a.day) == (b.year, b.day)) {
+        let mut answers = Vec::new();
+        for (solution, input) in day_solutions {
+            if let Some(solution_answers) = run_solution(solution, input, part, &bar, &mut output_log, pdb, timeout)? {
+                answers.extend(solution_answers);
+            } else {
+                failures += 1;
+                skips += 1;
+            }
+        }
+        mismatches += report_answers(config, &answers, submit, disable_submit_safety).await?;
+    }
+    if let Some(log) = log {
+        write(log, output_log)?;
+        info!("Solution output written to {}", log.display());
     }
     if import_failures > 0 {
         warn!("{import_failures} solution{} failed to import", if import_failures == 1 {""} else {"s"});
@@ -163,6 +256,9 @@ pub async fn run(config: &mut Config, year: Option<u16>, day: Option<u8>, part:
     if failures > 0 {
         warn!("{failures} solution{} failed", if failures == 1 {""} else {"s"});
     }
+    if mismatches > 0 {
+        warn!("{mismatches} part{} had implementations that disagree", if mismatches == 1 {""} else {"s"});
+    }
     let skips = skips - failures;
     if skips > 0 {
         warn!("{skips} solution{} were skipped", if skips == 1 {""} else {"s"});
@@ -0,0 +1,70 @@
+use std::env::current_dir;
+use std::path::Path;
+use std::time::Duration;
+use anyhow::{bail, Context, Result};
+use tokio::time::sleep;
+use tracing::{info, warn};
+use crate::calendar::{self, days_in, Defaults, When};
+use crate::{Config, NewArgs};
+use crate::commands::new::{fetch_puzzle, new};
+use crate::project::Settings;
+
+/// The longest `aoc start` will wait for a puzzle to unlock, in hours
+const MAX_WAIT: i64 = 24;
+/// How long after the unlock time to fetch, in case the local clock is ahead
+const UNLOCK_MARGIN: Duration = Duration::from_secs(2);
+/// How many times to fetch a puzzle that has only just unlocked, which fails if the local clock is too far ahead
+const FETCH_ATTEMPTS: u32 = 5;
+/// How long to wait between fetching a puzzle that has only just unlocked
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+pub async fn start(config: &mut Config, base_dir: &Path, settings: &Settings, year: Option<When>, day: Option<u8>) -> Result<()> {
+    let (year, day) = if year.is_none() {
+        calendar::next_puzzle()?
+    } else {
+        let defaults = Defaults::infer(base_dir, &current_dir()?, settings);
+        match defaults.select(year, day, None)? {
+            (Some(year), Some(day), None) => (year, day),
+            (_, _, Some(_)) => bail!("Only a year and day can be given"),
+            _ => bail!("Give the day to start as well as the year"),
+        }
+    };
+    if !(1..=days_in(year)).contains(&day) {
+        bail!("Invalid day: {day}, {year} has days 1 to {}", days_in(year));
+    }
+    let unlocks_at = calendar::unlocks_at(year, day);
+    let wait = unlocks_at - calendar::now();
+    if wait > chrono::Duration::hours(MAX_WAIT) {
+        bail!("{year} day {day} doesn't unlock until {unlocks_at}");
+    }
+    let attempts = if let Ok(wait) = wait.to_std() {
+        info!("Waiting {}s for {year} day {day} to unlock at {}", wait.as_secs(), unlocks_at.format("%H:%M %Z"));
+        sleep(wait + UNLOCK_MARGIN).await;
+        FETCH_ATTEMPTS
+    } else {
+        1
+    };
+    for attempt in 1..=attempts {
+        match fetch_puzzle(config, year, day).await {
+            Ok(_) => break,
+            Err(error) if attempt < attempts => {
+                warn!("Failed to fetch {year} day {day}, trying again in {}s: {error}", RETRY_DELAY.as_secs());
+                sleep(RETRY_DELAY).await;
+            }
+            Err(error) => return Err(error).with_context(|| format!("Failed to fetch {year} day {day}")),
+        }
+    }
+    info!("https://adventofcode.com/{year}/day/{day}");
+    let created = new(config, base_dir, settings, NewArgs {
+        template: settings.template().to_string(),
+        file: None,
+        year: Some(When::Year(year)),
+        day: Some(day),
+        part: None,
+        dry_run: false,
+    }).await?;
+    for path in created {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
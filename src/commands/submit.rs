@@ -0,0 +1,22 @@
+use anyhow::{bail, Result};
+use crate::calendar::days_in;
+use crate::Config;
+use crate::commands::run::{handle_result, Answer};
+use crate::project::{Settings, SubmitPolicy};
+
+/// Submits an answer that wasn't found by a solution, with the same safety checks as `aoc run --submit`
+pub async fn submit(config: &mut Config, settings: &Settings, year: u16, day: u8, part: u8, answer: &str, disable_submit_safety: bool) -> Result<()> {
+    if !(1..=days_in(year)).contains(&day) {
+        bail!("Invalid day: {day}, {year} has days 1 to {}", days_in(year));
+    }
+    let answer = answer.trim();
+    if answer.is_empty() {
+        bail!("The answer can't be empty");
+    }
+    // Asking is only for answers from solutions, as the answer was given explicitly
+    let policy = match settings.submit() {
+        SubmitPolicy::Never => bail!("Submitting is disabled by the `submit` setting"),
+        SubmitPolicy::Ask | SubmitPolicy::Always => SubmitPolicy::Always,
+    };
+    handle_result(config, &Answer::manual(year, day, part, answer.to_string()), policy, disable_submit_safety).await
+}
@@ -0,0 +1,48 @@
+use std::path::Path;
+use anyhow::{bail, Result};
+use tokio::fs;
+use tracing::info;
+use crate::display::{ActionType, println};
+use crate::project::Settings;
+use crate::templates::{File, Template};
+use crate::TemplateCommand;
+
+pub async fn template(base_dir: &Path, settings: &Settings, command: TemplateCommand) -> Result<()> {
+    match command {
+        TemplateCommand::List => {
+            for template in Template::all(base_dir, settings)? {
+                let description = template.description().map(|description| format!(" ({description})")).unwrap_or_default();
+                let path = template.path().map(|path| format!(" from {}", path.display())).unwrap_or_default();
+                println(template.origin, ActionType::Info, format!("{}{description}{path}", template.name));
+            }
+        }
+        TemplateCommand::Show { name } => {
+            let template = Template::find(base_dir, settings, &name)?;
+            for File { path, source } in template.files()? {
+                if let Some(path) = path {
+                    println("File", ActionType::Info, path);
+                }
+                print!("{source}");
+            }
+        }
+        TemplateCommand::Export { name, dir } => {
+            let template = Template::find(base_dir, settings, &name)?;
+            let dir = dir.unwrap_or_else(|| settings.templates(base_dir));
+            let files = template.files()?;
+            let single = dir.join(format!("{name}.tera"));
+            let directory = dir.join(&name);
+            if single.exists() || directory.exists() {
+                bail!("A template called `{name}` already exists in {}", dir.display());
+            }
+            for File { path, source } in files {
+                let path = path.map_or_else(|| single.clone(), |path| directory.join(path));
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+                fs::write(&path, source).await?;
+            }
+            info!("Exported `{name}` to {}", dir.display());
+        }
+    }
+    Ok(())
+}
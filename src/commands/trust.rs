@@ -1,15 +1,95 @@
-use std::path::Path;
-use tracing::info;
-use anyhow::Result;
-use crate::Config;
-use crate::display::confirm;
+use std::collections::HashSet;
+use std::path::{absolute, Path};
+use tracing::{info, warn};
+use anyhow::{bail, Result};
+use crate::{Config, Scope};
+use crate::display::{ActionType, confirm, println};
 
-pub fn trust(config: &mut Config, dir: &Path) -> Result<()> {
+/// Removes duplicate trusted directories, which older versions added every time a directory was trusted
+fn dedup(config: &mut Config) {
+    let mut seen = HashSet::new();
+    config.trusted_dirs.retain(|dir| seen.insert(dir.clone()));
+}
+
+/// Forgets everything about directories that are no longer trusted
+fn forget_untrusted(config: &mut Config) {
+    let trusted = &config.trusted_dirs;
+    config.trust_scopes.retain(|dir, _| trusted.contains(dir));
+    // Trusting a directory again starts afresh, without strict mode or anything approved in it before
+    config.strict_dirs.retain(|dir, _| trusted.contains(dir));
+}
+
+/// Trusts `dir` to `scope`, turning strict mode on or off for it if `strict` is set
+pub fn trust(config: &mut Config, dir: &Path, scope: Scope, strict: Option<bool>) -> Result<()> {
+    dedup(config);
     let dir = dir.canonicalize()?;
-    if !confirm(format!("Are you sure you want to trust {}?", dir.display()))? {
-        return Ok(());
+    if !config.trusted_dirs.contains(&dir) {
+        if !confirm(format!("Are you sure you want to trust {}?", dir.display()))? {
+            return Ok(());
+        }
+        config.trusted_dirs.push(dir.clone());
+        config.trust_scopes.insert(dir.clone(), scope);
+        info!("Successfully trusted {} to {scope} solutions!", dir.display());
+    } else if config.scope(&dir) == scope {
+        info!("{} is already trusted to {scope} solutions", dir.display());
+    } else {
+        config.trust_scopes.insert(dir.clone(), scope);
+        info!("{} is now trusted to {scope} solutions", dir.display());
+    }
+    match strict {
+        Some(true) => {
+            config.strict_dirs.entry(dir.clone()).or_default();
+            info!("Files in {} will only be imported once you've approved them", dir.display());
+        }
+        Some(false) => {
+            config.strict_dirs.remove(&dir);
+            info!("Files in {} will be imported without approving them", dir.display());
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+pub fn list_trusted(config: &mut Config) {
+    dedup(config);
+    if config.trusted_dirs.is_empty() {
+        warn!("No directories are trusted. Use `aoc trust <dir>` to trust one.");
+        return;
+    }
+    for dir in &config.trusted_dirs {
+        let scope = config.scope(dir);
+        let strict = config.strict_dirs.get(dir).map_or(String::new(), |approvals| {
+            let count = approvals.len();
+            format!(", strict with {count} approved file{}", if count == 1 {""} else {"s"})
+        });
+        if dir.is_dir() {
+            println("Trusted", ActionType::Success, format!("{} (to {scope}{strict})", dir.display()));
+        } else {
+            println("Missing", ActionType::Warning, format!("{} (to {scope}{strict}), no longer exists", dir.display()));
+        }
+    }
+    if config.trusted_dirs.iter().any(|dir| !dir.is_dir()) {
+        info!("Use `aoc untrust --missing` to stop trusting directories that no longer exist");
+    }
+}
+
+/// Stops trusting `dir`, or every directory that no longer exists if `missing` is set
+pub fn untrust(config: &mut Config, dir: Option<&Path>, missing: bool) -> Result<()> {
+    dedup(config);
+    if missing {
+        let before = config.trusted_dirs.len();
+        config.trusted_dirs.retain(|dir| dir.is_dir());
+        let removed = before - config.trusted_dirs.len();
+        info!("Stopped trusting {removed} director{} that no longer exist{}", if removed == 1 {"y"} else {"ies"}, if removed == 1 {"s"} else {""});
+    } else if let Some(dir) = dir {
+        // The directory may have been deleted, in which case it can't be canonicalized
+        let dir = dir.canonicalize().or_else(|_| absolute(dir))?;
+        let Some(index) = config.trusted_dirs.iter().position(|trusted| *trusted == dir) else {
+            bail!("{} isn't trusted", dir.display());
+        };
+        config.trusted_dirs.remove(index);
+        info!("Stopped trusting {}", dir.display());
     }
-    config.trusted_dirs.push(dir.clone());
-    info!("Successfully trusted {}!", dir.display());
+    forget_untrusted(config);
     Ok(())
 }
@@ -0,0 +1,174 @@
+use std::collections::BTreeSet;
+use std::env::current_dir;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+use anyhow::Result;
+use futures::future::try_join_all;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc::{Receiver, channel};
+use tokio::time::timeout;
+use tracing::{error, info};
+use crate::Config;
+use crate::calendar::{Defaults, When};
+use crate::commands::run::{get_input, report_answers, run_solution, select_solutions};
+use crate::display::{ActionType, println, progress_bar};
+use crate::integrity::approve;
+use crate::project::{Settings, SubmitPolicy};
+use crate::runner::{self, in_roots, in_skipped_dir, Runner, runner_for};
+use crate::solutions::{Solution, unregister_file};
+
+/// How long to wait for more changes after one is seen, editors often write files in several steps
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Runs `solution` against the examples in its day's cached puzzle, checking its answers against the puzzle's
+///
+/// Part one's example is usually the first in the puzzle and part two's the last.
+/// Anything the solution prints is hidden, and failures are only reported briefly, as they're shown when it's run against the input.
+fn check_examples(config: &Config, solution: &Solution, part: Option<u8>, timeout: Option<Duration>) -> Result<()> {
+    let Some(puzzle) = config.days.get(&solution.year).and_then(|days| days.get(&solution.day)).and_then(|day| day.puzzle.as_ref()) else {
+        return Ok(());
+    };
+    for (example_part, expected) in (1..=2).zip(&puzzle.answers) {
+        let Some(expected) = expected else {
+            continue;
+        };
+        if part.is_some_and(|part| part != example_part) || !solution.parts().contains(&example_part) {
+            continue;
+        }
+        let example = if example_part == 1 { puzzle.examples.first() } else { puzzle.examples.last() };
+        let Some(example) = example else {
+            continue;
+        };
+        let identifier = format!("{} day {} part {example_part} ({})", solution.year, solution.day, solution.name);
+        let (results, _) = solution.run(example, timeout)?;
+        match results.map(|results| results.into_iter().find(|(part, _)| *part == example_part)) {
+            Ok(Some((_, answer))) if answer == *expected => println("Example", ActionType::Success, identifier),
+            Ok(Some((_, answer))) => println("Example", ActionType::Failure, format!("{identifier}: {answer}, expected {expected}")),
+            Ok(None) => {}
+            Err(_) => println("Example", ActionType::Failure, format!("{identifier}: failed")),
+        }
+    }
+    Ok(())
+}
+
+/// Runs `solutions` without submitting against their examples and inputs, fetching any inputs that haven't been cached yet
+async fn run_solutions(config: &mut Config, solutions: &[Solution], part: Option<u8>, timeout: Option<Duration>) -> Result<()> {
+    let new_inputs = RwLock::new(Vec::new());
+    let solutions = try_join_all(solutions.iter()
+        .map(|solution| get_input(config, solution, &new_inputs))
+    ).await?;
+    let new_inputs = new_inputs.into_inner().unwrap();
+    if !new_inputs.is_empty() {
+        for (year, day, input) in new_inputs {
+            config.day(year, day).input = Some(input);
+        }
+        // Watching only stops when interrupted, so new inputs are saved straight away
+        confy::store(env!("CARGO_CRATE_NAME"), None, &*config)?;
+    }
+
+    for (solution, _) in &solutions {
+        check_examples(config, solution, part, timeout)?;
+    }
+    let bar = progress_bar("Running".to_string(), ActionType::Progress, solutions.len() as u64)?;
+    let mut output_log = String::new();
+    for day_solutions in solutions.chunk_by(|(a, _), (b, _)| (a.year, a.day) == (b.year, b.day)) {
+        let mut answers = Vec::new();
+        for (solution, input) in day_solutions {
+            if let Some(solution_answers) = run_solution(solution, input, part, &bar, &mut output_log, false, timeout)? {
+                answers.extend(solution_answers);
+            }
+        }
+        report_answers(config, &answers, SubmitPolicy::Never, false).await?;
+    }
+    bar.finish_and_clear();
+    Ok(())
+}
+
+/// Waits for solution files in `roots` to change, returning their paths relative to `cwd`
+async fn next_changes(receiver: &mut Receiver<notify::Result<Event>>, cwd: &Path, runners: &[Box<dyn Runner>], roots: Option<&[PathBuf]>) -> Result<BTreeSet<PathBuf>> {
+    let mut changed = BTreeSet::new();
+    while changed.is_empty() {
+        let Some(event) = receiver.recv().await else {
+            anyhow::bail!("Stopped receiving file changes");
+        };
+        let mut events = vec![event];
+        while let Ok(Some(event)) = timeout(DEBOUNCE, receiver.recv()).await {
+            events.push(event);
+        }
+        for event in events {
+            let event = event?;
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+                continue;
+            }
+            changed.extend(event.paths.into_iter()
+                .filter_map(|file| file.strip_prefix(cwd).map(Path::to_path_buf).ok())
+                .filter(|file| !in_skipped_dir(file) && runner_for(runners, file).is_some() && in_roots(roots, file)));
+        }
+    }
+    Ok(changed)
+}
+
+pub async fn watch(config: &mut Config, base_dir: &Path, settings: &Settings, year: Option<When>, day: Option<u8>, part: Option<u8>) -> Result<()> {
+    let cwd = current_dir()?;
+    let (year, day, part) = Defaults::infer(base_dir, &cwd, settings).select(year, day, part)?;
+    let timeout = settings.timeout()?;
+    let roots = settings.roots(base_dir);
+    let runners = runner::runners(base_dir, settings, config.approvals(base_dir))?;
+    runner::load_solutions(&runners, roots.as_deref(), config.approvals(base_dir))?;
+    if let Err(error) = run_solutions(config, &select_solutions(year, day, part), part, timeout).await {
+        error!("{error}");
+    }
+
+    let (sender, mut receiver) = channel(64);
+    let mut watcher = notify::recommended_watcher(move |event| {
+        sender.blocking_send(event).ok();
+    })?;
+    watcher.watch(&cwd, RecursiveMode::Recursive)?;
+    info!("Watching for changes, press Ctrl+C to stop");
+    loop {
+        for file in next_changes(&mut receiver, &cwd, &runners, roots.as_deref()).await? {
+            // A failure only affects this change, so it's reported and watching carries on
+            if let Err(error) = handle_change(config, base_dir, &cwd, &runners, &file, (year, day, part), timeout).await {
+                error!("{}: {error}", file.display());
+            }
+        }
+    }
+}
+
+/// Re-imports a changed file and re-runs its selected solutions
+async fn handle_change(config: &mut Config, base_dir: &Path, cwd: &Path, runners: &[Box<dyn Runner>], file: &Path, (year, day, part): (Option<u16>, Option<u8>, Option<u8>), timeout: Option<Duration>) -> Result<()> {
+    let Some(runner) = runner_for(runners, file) else {
+        return Ok(());
+    };
+    unregister_file(&cwd.join(file));
+    if !file.exists() {
+        println("Removed", ActionType::Info, file.display());
+        return Ok(());
+    }
+    println("Changed", ActionType::Info, file.display());
+    if let Some(approvals) = config.approvals(base_dir) {
+        let sources = match runner.sources(file) {
+            Ok(sources) => sources,
+            Err(error) => {
+                println("Skipped", ActionType::Warning, format!("{}: {error}", file.display()));
+                return Ok(());
+            }
+        };
+        let rejected = !approve(approvals, sources.iter().map(PathBuf::as_path))?.is_empty();
+        confy::store(env!("CARGO_CRATE_NAME"), None, &*config)?;
+        if rejected {
+            println("Skipped", ActionType::Warning, format!("{}, it or files it runs weren't approved", file.display()));
+            return Ok(());
+        }
+    }
+    if let Err(error) = runner.load(file) {
+        error!("Failed to import {}\n\n{error}", file.display());
+        return Ok(());
+    }
+    let file = cwd.join(file).canonicalize().ok();
+    let solutions: Vec<_> = select_solutions(year, day, part).into_iter()
+        .filter(|solution| solution.location.as_ref().map(|location| &location.file) == file.as_ref())
+        .collect();
+    run_solutions(config, &solutions, part, timeout).await
+}
@@ -15,6 +15,8 @@ use tracing::metadata::LevelFilter;
 use tracing::span::{Attributes, Record};
 use crate::value_enum;
 
+mod traceback;
+
 const INDENT: u8 = 12;
 
 lazy_static! {
@@ -53,6 +55,11 @@ pub fn spinner(action: String, action_type: ActionType, message: String) -> Resu
     Ok(bar)
 }
 
+/// Hides the progress bars while `f` runs, for anything that needs the terminal to itself
+pub fn suspend<T>(f: impl FnOnce() -> T) -> T {
+    PROGRESS.suspend(f)
+}
+
 pub fn confirm(prompt: impl Into<String>) -> Result<bool> {
     Ok(PROGRESS.suspend(|| dialoguer::Confirm::new()
         .with_prompt(prompt)
@@ -107,6 +114,40 @@ pub fn println(action: impl Display, action_type: ActionType, msg: impl Display)
     eprintln_safe!("{} {msg}", action_type.value().apply_to(pad_str(&action.to_string(), INDENT as usize, Alignment::Right, None)));
 }
 
+/// How many lines of a solution's output are shown without `-v`
+const COLLAPSED_OUTPUT_LINES: usize = 5;
+
+/// Prints output captured from a solution, collapsed to the first few lines unless running with `-v`
+pub fn print_output(output: &str) {
+    print_lines(output, tracing::enabled!(Level::DEBUG));
+}
+
+/// Prints output in the same style as [`print_output`], but never collapsed
+pub fn print_full_output(output: &str) {
+    print_lines(output, true);
+}
+
+fn print_lines(output: &str, full: bool) {
+    let lines: Vec<_> = output.lines().collect();
+    if lines.is_empty() {
+        return;
+    }
+    let shown = if full { lines.len() } else { lines.len().min(COLLAPSED_OUTPUT_LINES) };
+    let leading = ActionType::Debug.value().apply_to(pad_str("│", INDENT as usize + 1, Alignment::Right, None));
+    PROGRESS.suspend(|| {
+        for line in &lines[..shown] {
+            eprintln!("{leading} {line}");
+        }
+        if shown < lines.len() {
+            let hidden = lines.len() - shown;
+            eprintln!("{leading} {}", ActionType::Debug.value().apply_to(format!(
+                "... {hidden} more line{}, use -v to show all output",
+                if hidden == 1 {""} else {"s"},
+            )));
+        }
+    });
+}
+
 fn format_debug_info(field: impl Display, value: impl Display) -> String {
     let leading = pad_str("└──", INDENT as usize + 1, Alignment::Right, None);
     let field = format!("{field}");
@@ -237,8 +278,10 @@ pub trait FormatTraceBack<T> {
 }
 
 fn format_trace_back(error: PyErr) -> String {
-    // TODO: Add fancy formatting
     Python::with_gil(|py| {
+        if let Ok(formatted) = traceback::format(py, &error) {
+            return formatted;
+        }
         py.import("traceback").unwrap()
             .getattr("format_exception").unwrap()
             .call1((error,)).unwrap()
@@ -247,11 +290,12 @@ fn format_trace_back(error: PyErr) -> String {
     })
 }
 
+pub fn traceback_error(error: PyErr) -> anyhow::Error {
+    anyhow!("Uncaught python exception:\n\n{}", format_trace_back(error))
+}
+
 impl<T> FormatTraceBack<T> for PyResult<T> {
     fn tb(self) -> Result<T> {
-        match self {
-            Ok(result) => Ok(result),
-            Err(error) => Err(anyhow!("Uncaught python exception:\n\n{}", format_trace_back(error))),
-        }
+        self.map_err(traceback_error)
     }
 }
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use console::Style;
+use pyo3::{PyAny, PyErr, PyResult, Python};
+use tracing::Level;
+use crate::display::ActionType;
+
+/// Lines of source shown either side of the line that raised
+const CONTEXT_LINES: usize = 2;
+/// Locals with longer reprs are truncated
+const MAX_LOCAL_LENGTH: usize = 120;
+
+const KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else", "except",
+    "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "match", "case", "nonlocal", "not", "or",
+    "pass", "raise", "return", "try", "while", "with", "yield",
+];
+const CONSTANTS: &[&str] = &["True", "False", "None", "self"];
+
+/// Whether a frame belongs to the import machinery the runner imports solutions with, or the embedded `aoc` module,
+/// rather than the user's code, whose modules the runner names `aoc_...`
+fn is_internal(file: &str, module: Option<&str>) -> bool {
+    file.starts_with("<frozen importlib") || module.is_some_and(|module| module == "aoc" || module.starts_with("aoc."))
+}
+
+/// Applies basic Python syntax highlighting to a single line
+fn highlight(line: &str) -> String {
+    let keyword = Style::new().magenta().bold();
+    let constant = Style::new().cyan();
+    let string = Style::new().green();
+    let number = Style::new().cyan();
+    let comment = Style::new().dim();
+
+    let mut highlighted = String::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c == '#' {
+            write!(highlighted, "{}", comment.apply_to(&line[start..])).ok();
+            break;
+        }
+        if c == '"' || c == '\'' {
+            let mut end = line.len();
+            let mut escaped = false;
+            for (i, next) in chars.by_ref() {
+                if next == c && !escaped {
+                    end = i + 1;
+                    break;
+                }
+                escaped = next == '\\' && !escaped;
+            }
+            write!(highlighted, "{}", string.apply_to(&line[start..end])).ok();
+            continue;
+        }
+        if c.is_alphanumeric() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some((i, next)) = chars.next_if(|(_, next)| next.is_alphanumeric() || *next == '_') {
+                end = i + next.len_utf8();
+            }
+            let word = &line[start..end];
+            let style = if c.is_ascii_digit() {
+                Some(&number)
+            } else if KEYWORDS.contains(&word) {
+                Some(&keyword)
+            } else if CONSTANTS.contains(&word) {
+                Some(&constant)
+            } else {
+                None
+            };
+            match style {
+                Some(style) => write!(highlighted, "{}", style.apply_to(word)).ok(),
+                None => write!(highlighted, "{word}").ok(),
+            };
+            continue;
+        }
+        highlighted.push(c);
+    }
+    highlighted
+}
+
+fn format_frame(py: Python, traceback: &PyAny, show_locals: bool, out: &mut String) -> PyResult<()> {
+    let frame = traceback.getattr("tb_frame")?;
+    let code = frame.getattr("f_code")?;
+    let file: String = code.getattr("co_filename")?.extract()?;
+    let function: String = code.getattr("co_name")?.extract()?;
+    let line: usize = traceback.getattr("tb_lineno")?.extract()?;
+    let location = Style::new().bold().apply_to(format!("{file}:{line}"));
+    let function = ActionType::Info.value().apply_to(function);
+    writeln!(out, "  {location} in {function}").ok();
+
+    let linecache = py.import("linecache")?;
+    // The file may have changed since it was cached, e.g. while watching
+    linecache.call_method1("checkcache", (&file,))?;
+    let first = line.saturating_sub(CONTEXT_LINES).max(1);
+    let width = (line + CONTEXT_LINES).to_string().len();
+    let mut source = Vec::new();
+    for number in first..=line + CONTEXT_LINES {
+        let text: String = linecache.call_method1("getline", (&file, number))?.extract()?;
+        if text.is_empty() && number > line {
+            break;
+        }
+        source.push((number, text.trim_end().to_string()));
+    }
+    // Dedent so deeply nested code doesn't push everything off the screen
+    let indent = source.iter()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(_, text)| text.len() - text.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    for (number, text) in &source {
+        let text = text.get(indent..).unwrap_or_default();
+        if *number == line {
+            let marker = ActionType::Failure.value();
+            writeln!(out, "  {} {} {}", marker.apply_to(format!("> {number:>width$}")), marker.apply_to("│"), highlight(text)).ok();
+        } else {
+            let dim = Style::new().dim();
+            writeln!(out, "    {} {} {}", dim.apply_to(format!("{number:>width$}")), dim.apply_to("│"), highlight(text)).ok();
+        }
+    }
+
+    if show_locals {
+        let locals = frame.getattr("f_locals")?;
+        let reprlib = py.import("reprlib")?;
+        for (name, value) in locals.call_method0("items")?.iter()?.map(|item| item?.extract::<(String, &PyAny)>()).collect::<PyResult<Vec<_>>>()? {
+            if name.starts_with("__") {
+                continue;
+            }
+            let mut value: String = reprlib.call_method1("repr", (value,))?.extract()?;
+            if value.len() > MAX_LOCAL_LENGTH {
+                let end = (0..=MAX_LOCAL_LENGTH).rev().find(|i| value.is_char_boundary(*i)).unwrap_or(0);
+                value.truncate(end);
+                value.push_str("...");
+            }
+            writeln!(out, "      {} = {value}", ActionType::Debug.value().apply_to(name)).ok();
+        }
+    }
+    Ok(())
+}
+
+/// Formats `exception` after the exceptions it was raised from, skipping any in `seen` so cycles of causes end
+fn format_exception(py: Python, exception: &PyAny, show_locals: bool, seen: &mut HashSet<usize>, out: &mut String) -> PyResult<()> {
+    seen.insert(exception.as_ptr() as usize);
+    let cause = exception.getattr("__cause__")?;
+    let context = exception.getattr("__context__")?;
+    if !cause.is_none() && !seen.contains(&(cause.as_ptr() as usize)) {
+        format_exception(py, cause, show_locals, seen, out)?;
+        writeln!(out, "\nThe above exception was the direct cause of the following exception:\n").ok();
+    } else if cause.is_none() && !context.is_none() && !seen.contains(&(context.as_ptr() as usize)) && !exception.getattr("__suppress_context__")?.is_true()? {
+        format_exception(py, context, show_locals, seen, out)?;
+        writeln!(out, "\nDuring handling of the above exception, another exception occurred:\n").ok();
+    }
+
+    let mut traceback = exception.getattr("__traceback__")?;
+    if !traceback.is_none() {
+        writeln!(out, "{}", Style::new().bold().apply_to("Traceback (most recent call last):")).ok();
+    }
+    while !traceback.is_none() {
+        let frame = traceback.getattr("tb_frame")?;
+        let file: String = frame.getattr("f_code")?.getattr("co_filename")?.extract()?;
+        let module: Option<String> = frame.getattr("f_globals")?.get_item("__name__").ok().and_then(|name| name.extract().ok());
+        if !is_internal(&file, module.as_deref()) {
+            format_frame(py, traceback, show_locals, out)?;
+        }
+        traceback = traceback.getattr("tb_next")?;
+    }
+
+    // `format_exception_only` handles the special formatting of syntax errors and exception notes
+    let lines: Vec<String> = py.import("traceback")?
+        .call_method1("format_exception_only", (exception,))?
+        .extract()?;
+    for line in lines.concat().lines() {
+        if line.starts_with(char::is_whitespace) {
+            writeln!(out, "{line}").ok();
+        } else {
+            let (name, rest) = line.split_once(':').map_or((line, None), |(name, rest)| (name, Some(rest)));
+            write!(out, "{}", ActionType::Failure.value().apply_to(name)).ok();
+            writeln!(out, "{}", rest.map(|rest| format!(":{rest}")).unwrap_or_default()).ok();
+        }
+    }
+    Ok(())
+}
+
+/// Formats a Python exception and its traceback, showing the source around each frame,
+/// and the local variables in each frame when running with `-vv`
+pub fn format(py: Python, error: &PyErr) -> PyResult<String> {
+    let mut out = String::new();
+    let exception = error.value(py);
+    // Exceptions that weren't caught in Python only have their traceback stored on the `PyErr`
+    if exception.getattr("__traceback__")?.is_none() {
+        exception.setattr("__traceback__", error.traceback(py))?;
+    }
+    format_exception(py, exception, tracing::enabled!(Level::TRACE), &mut HashSet::new(), &mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::types::PyModule;
+    use super::*;
+
+    #[test]
+    fn test_highlight_preserves_text() {
+        console::set_colors_enabled(false);
+        for line in [
+            "def part1(data: str) -> int:  # comment",
+            "    return sum(int(x) for x in data.split('\\n') if x != \"\")",
+            "print(f'{x!r}', 1_000, None)",
+            "s = 'unterminated",
+        ] {
+            assert_eq!(highlight(line), line);
+        }
+    }
+
+    #[test]
+    fn test_internal_frames() {
+        console::set_colors_enabled(false);
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let internal = PyModule::from_code(py, "def fail():\n    raise ValueError('internal')\n", "aoc_internal.py", "aoc.internal").unwrap();
+            let solution = PyModule::from_code(py, "def solve(f):\n    f()\n", "day01.py", "aoc_day01_py").unwrap();
+            let error = solution.getattr("solve").unwrap().call1((internal.getattr("fail").unwrap(),)).unwrap_err();
+            let formatted = format(py, &error).unwrap();
+            assert!(formatted.contains("day01.py:2 in solve"));
+            assert!(!formatted.contains("aoc_internal.py"));
+        });
+        assert!(is_internal("<frozen importlib._bootstrap>", Some("importlib._bootstrap")));
+        assert!(!is_internal("aoc_tools.py", Some("aoc_tools")));
+    }
+
+    #[test]
+    fn test_cause_cycle() {
+        console::set_colors_enabled(false);
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let locals = pyo3::types::PyDict::new(py);
+            py.run("a = ValueError('a')\nb = KeyError('b')\na.__cause__ = b\nb.__cause__ = a", None, Some(locals)).unwrap();
+            let error = PyErr::from_value(locals.get_item("a").unwrap().unwrap());
+            let formatted = format(py, &error).unwrap();
+            assert_eq!(formatted.matches("ValueError").count(), 1);
+            assert_eq!(formatted.matches("KeyError").count(), 1);
+        });
+    }
+}
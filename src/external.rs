@@ -0,0 +1,260 @@
+//! Solutions in any language, run as commands configured in `aoc.toml`
+//!
+//! The input is written to the command's stdin, and it prints the answer on the last line of stdout.
+
+use std::any::Any;
+use std::env::current_dir;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tracing::error;
+use crate::display::{ActionType, println};
+use crate::project::{self, Project};
+use crate::runner::{Runner, wait_with_timeout};
+use crate::solutions::{Failure, Implementation, Outcome, register, Solution, validate};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalSolution {
+    year: u16,
+    day: u8,
+    /// The part this solves, or `None` if it solves both and prints part 1 then part 2 on the last two lines
+    part: Option<u8>,
+    /// Run by the shell in the trusted directory, with `{year}`, `{day}` and `{part}` replaced
+    command: String,
+    #[serde(default)]
+    alternative: bool,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl ExternalSolution {
+    fn command(&self) -> String {
+        let command = self.command
+            .replace("{year}", &self.year.to_string())
+            .replace("{day}", &self.day.to_string());
+        match self.part {
+            Some(part) => command.replace("{part}", &part.to_string()),
+            None => command,
+        }
+    }
+
+    /// Commands that solve both parts have no part to replace `{part}` with
+    fn validate_command(&self) -> Result<(), String> {
+        if self.part.is_none() && self.command.contains("{part}") {
+            return Err("`{part}` can only be used in commands that solve a single part, set `part` or remove it".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Implementation for ExternalSolution {
+    /// Runs the command, anything it prints before the answer is treated as output
+    fn run(&self, input: &str, timeout: Option<Duration>) -> Result<Outcome> {
+        let command = self.command();
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        let mut child = shell
+            .arg(&command)
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run {command}"))?;
+        let mut stdin = child.stdin.take().unwrap();
+        let input = input.to_string();
+        // Written from another thread, as the command may print a lot before it reads its input
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let Some(result) = wait_with_timeout(child, timeout)? else {
+            return Ok((Err(Failure::timed_out(timeout.unwrap_or_default())), String::new()));
+        };
+        match writer.join().unwrap() {
+            // The command doesn't have to read its input
+            Err(error) if error.kind() != ErrorKind::BrokenPipe => return Err(error.into()),
+            _ => {}
+        }
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let stderr = String::from_utf8_lossy(&result.stderr).into_owned();
+        if !result.status.success() {
+            let message = match stderr.trim() {
+                "" => format!("{command} exited with {}", result.status),
+                stderr => stderr.to_string(),
+            };
+            return Ok((Err(Failure::new(message)), stdout.into_owned()));
+        }
+
+        let mut lines: Vec<_> = stdout.trim_end().lines().collect();
+        let parts: &[u8] = match self.part {
+            Some(1) => &[1],
+            Some(2) => &[2],
+            _ => &[1, 2],
+        };
+        if lines.len() < parts.len() {
+            let message = format!("{command} printed {} lines, expected an answer for each of parts {parts:?}", lines.len());
+            return Ok((Err(Failure::new(message)), stdout.into_owned() + &stderr));
+        }
+        let answers = lines.split_off(lines.len() - parts.len());
+        let answers = parts.iter().copied()
+            .zip(answers.into_iter().map(|answer| answer.trim().to_string()))
+            .collect();
+        let mut output = lines.join("\n");
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        Ok((Ok(answers), output + &stderr))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Loads the commands configured in `aoc.toml`
+pub struct ExternalRunner {
+    base_dir: PathBuf,
+}
+
+impl ExternalRunner {
+    pub fn new(base_dir: &Path) -> Self {
+        Self {
+            base_dir: base_dir.to_path_buf(),
+        }
+    }
+
+    fn file(&self) -> PathBuf {
+        self.base_dir.join(project::FILE)
+    }
+}
+
+impl Runner for ExternalRunner {
+    fn name(&self) -> &'static str {
+        "Commands"
+    }
+
+    fn handles(&self, file: &Path) -> bool {
+        current_dir().is_ok_and(|cwd| cwd.join(file) == self.file())
+    }
+
+    /// `aoc.toml` is in the trusted directory, which may be above the current directory
+    fn discover(&self, _files: &[PathBuf]) -> Vec<PathBuf> {
+        let file = self.file();
+        if file.exists() {
+            vec![file]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Commands can run any file, so only an `aoc.toml` without solutions can be loaded in strict mode
+    fn sources(&self, file: &Path) -> Result<Vec<PathBuf>> {
+        if !Project::load(&self.base_dir)?.solutions.is_empty() {
+            bail!("Commands can run any file, so they can't be checked in strict mode");
+        }
+        Ok(vec![file.to_path_buf()])
+    }
+
+    fn load(&self, _file: &Path) -> Result<u16> {
+        let project = Project::load(&self.base_dir)?;
+        let mut failures = 0u16;
+        for entry in &project.solutions {
+            let location = project.location(&entry.span());
+            let external = ExternalSolution {
+                dir: self.base_dir.clone(),
+                ..entry.get_ref().clone()
+            };
+            let solution = Solution {
+                year: external.year,
+                day: external.day,
+                part: external.part,
+                name: external.command.clone(),
+                location: Some(location.clone()),
+                implementation: Arc::new(external.clone()),
+            };
+            let result = validate(external.year, external.day, external.part).and_then(|()| external.validate_command()).and_then(|()| {
+                register(solution, external.alternative)
+                    .map_err(|error| format!("{error}, set `alternative = true` to register another implementation of it"))
+            });
+            if let Err(error) = result {
+                error!("{location}: {error}");
+                failures += 1;
+            }
+        }
+        Ok(failures)
+    }
+
+    fn describe(&self) -> Result<()> {
+        let file = self.file();
+        if file.exists() {
+            let project = Project::load(&self.base_dir)?;
+            println("Commands", ActionType::Info, format!("{} in {}", project.solutions.len(), file.display()));
+        } else {
+            println("Commands", ActionType::Info, format!("none, {} doesn't exist", file.display()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn run(command: &str, part: Option<u8>, input: &str) -> (Result<Vec<(u8, String)>, String>, String) {
+        let solution = ExternalSolution {
+            year: 2023,
+            day: 1,
+            part,
+            command: command.to_string(),
+            alternative: false,
+            dir: std::env::temp_dir(),
+        };
+        let (answers, output) = solution.run(input, None).unwrap();
+        (answers.map_err(|failure| failure.message), output)
+    }
+
+    #[test]
+    fn test_answers() {
+        assert_eq!(run("echo debug; echo {year} {day} {part}", Some(2), ""), (Ok(vec![(2, "2023 1 2".to_string())]), "debug\n".to_string()));
+        assert_eq!(run("echo debug; echo 1; echo ' 2 '", None, ""), (Ok(vec![(1, "1".to_string()), (2, "2".to_string())]), "debug\n".to_string()));
+        assert_eq!(run("cat", Some(1), "a\nb\n"), (Ok(vec![(1, "b".to_string())]), "a\n".to_string()));
+    }
+
+    #[test]
+    fn test_output() {
+        let (answers, output) = run("echo warning >&2; echo 5", Some(1), "");
+        assert_eq!(answers, Ok(vec![(1, "5".to_string())]));
+        assert_eq!(output, "warning\n");
+        let (answers, output) = run("echo 1", None, "");
+        assert!(answers.unwrap_err().contains("printed 1 lines"));
+        assert_eq!(output, "1\n");
+        let (answers, _) = run("echo oops >&2; exit 3", Some(1), "");
+        assert_eq!(answers.unwrap_err(), "oops");
+    }
+
+    #[test]
+    fn test_validate_command() {
+        let solution = |part, command: &str| ExternalSolution {
+            year: 2023,
+            day: 1,
+            part,
+            command: command.to_string(),
+            alternative: false,
+            dir: PathBuf::new(),
+        };
+        assert!(solution(None, "./day01 {part}").validate_command().is_err());
+        assert!(solution(Some(1), "./day01 {part}").validate_command().is_ok());
+        assert!(solution(None, "./day01").validate_command().is_ok());
+    }
+}
@@ -0,0 +1,111 @@
+//! Strict trust, where solution files are only imported once their contents have been approved
+//!
+//! Trusting a directory normally trusts anything that later appears in it, e.g. from a `git pull`.
+//! In strict mode the hash of every approved file is recorded, and new or changed files are shown
+//! as a diff against what was approved before they can be imported.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs::read;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use similar::{ChangeTag, TextDiff};
+use crate::display::{ActionType, confirm, print_full_output, println};
+
+/// Approved files, keyed by their canonical path
+pub type Approvals = BTreeMap<PathBuf, Approved>;
+
+/// The contents of a file when it was approved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Approved {
+    /// SHA-256 of the contents, in hex
+    hash: String,
+    /// Kept to show what changed since, empty for binary files
+    contents: String,
+}
+
+impl Approved {
+    fn new(contents: &[u8]) -> Self {
+        Self {
+            hash: hash(contents),
+            contents: String::from_utf8(contents.to_vec()).unwrap_or_default(),
+        }
+    }
+}
+
+fn hash(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+/// Summarises the lines added and removed since `before`, with the changed lines themselves
+fn diff(before: &str, after: &str) -> (String, String) {
+    let diff = TextDiff::from_lines(before, after);
+    let (mut added, mut removed) = (0, 0);
+    let mut lines = String::new();
+    for change in diff.iter_all_changes() {
+        let (sign, style) = match change.tag() {
+            ChangeTag::Equal => continue,
+            ChangeTag::Insert => {
+                added += 1;
+                ('+', ActionType::Success.value())
+            }
+            ChangeTag::Delete => {
+                removed += 1;
+                ('-', ActionType::Failure.value())
+            }
+        };
+        writeln!(lines, "{}", style.apply_to(format!("{sign} {}", change.value().trim_end()))).ok();
+    }
+    (format!("{added} line{} added, {removed} removed", if added == 1 {""} else {"s"}), lines)
+}
+
+/// Asks whether new or changed files can be imported, showing what changed in each,
+/// returning the files that weren't approved
+pub fn approve<'a>(approvals: &mut Approvals, files: impl IntoIterator<Item = &'a Path>) -> Result<Vec<PathBuf>> {
+    let mut pending = Vec::new();
+    for file in files {
+        let contents = read(file)?;
+        let path = file.canonicalize()?;
+        let previous = approvals.get(&path);
+        if previous.is_some_and(|previous| previous.hash == hash(&contents)) {
+            continue;
+        }
+        let action = if previous.is_some() { "Modified" } else { "New" };
+        match std::str::from_utf8(&contents) {
+            Ok(text) => {
+                let (summary, lines) = diff(previous.map_or("", |previous| &previous.contents), text);
+                println(action, ActionType::Warning, format!("{}: {summary}", file.display()));
+                // Shown in full, as anything hidden could be what shouldn't be approved
+                print_full_output(&lines);
+            }
+            Err(_) => println(action, ActionType::Warning, format!("{}: binary file", file.display())),
+        }
+        pending.push((file.to_path_buf(), path, contents));
+    }
+    if pending.is_empty() {
+        return Ok(Vec::new());
+    }
+    let count = pending.len();
+    if !confirm(format!("Import {count} new or modified file{}?", if count == 1 {""} else {"s"}))? {
+        return Ok(pending.into_iter().map(|(file, _, _)| file).collect());
+    }
+    for (_, path, contents) in pending {
+        approvals.insert(path, Approved::new(&contents));
+    }
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        console::set_colors_enabled(false);
+        let (summary, lines) = diff("a\nb\nc\n", "a\nc\nd\ne\n");
+        assert_eq!(summary, "2 lines added, 1 removed");
+        assert_eq!(lines, "- b\n+ d\n+ e\n");
+    }
+}
@@ -1,32 +1,71 @@
 #![warn(clippy::pedantic)]
 
 mod api;
+mod calendar;
 mod commands;
 mod python;
+mod native;
+mod external;
+mod integrity;
+mod project;
+mod runner;
+mod solutions;
+mod templates;
+mod wasm;
 mod display;
 mod value_enum;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env::current_dir;
-use std::path::PathBuf;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 use std::process::exit;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use anyhow::{ bail, Result};
 use clap_verbosity_flag::{InfoLevel, Verbosity};
 use serde::{Deserialize, Serialize};
 use tracing::{error, trace};
 use tracing_log::AsTrace;
-use crate::api::Submission;
+use crate::api::{Puzzle, Submission};
+use crate::calendar::When;
 use crate::display::Logger;
+use crate::integrity::Approvals;
+use crate::project::Settings;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct Config {
     token: Option<String>,
     trusted_dirs: Vec<PathBuf>,
+    /// What the CLI may do in each trusted directory, directories without a scope allow everything
+    #[serde(default)]
+    trust_scopes: BTreeMap<PathBuf, Scope>,
+    /// Settings for every project, which each project's `aoc.toml` can override
+    #[serde(default)]
+    settings: Settings,
+    /// Files approved in each trusted directory in strict mode, directories without an entry trust everything in them
+    #[serde(default)]
+    strict_dirs: BTreeMap<PathBuf, Approvals>,
     days: HashMap<u16, HashMap<u8, Day>>,
 }
 
 impl Config {
+    /// The most specific trusted directory containing `dir`
+    pub fn trusted_dir(&self, dir: &Path) -> Option<&Path> {
+        self.trusted_dirs.iter()
+            .filter(|trusted| dir.starts_with(trusted))
+            .max_by_key(|trusted| trusted.components().count())
+            .map(PathBuf::as_path)
+    }
+
+    pub fn scope(&self, trusted_dir: &Path) -> Scope {
+        self.trust_scopes.get(trusted_dir).copied().unwrap_or(Scope::Submit)
+    }
+
+    /// The files approved in `trusted_dir`, or `None` if it isn't in strict mode
+    pub fn approvals(&mut self, trusted_dir: &Path) -> Option<&mut Approvals> {
+        self.strict_dirs.get_mut(trusted_dir)
+    }
+
     pub fn get_input(&self, year: u16, day: u8) -> Option<String> {
         self.days.get(&year)?.get(&day)?.input.clone()
     }
@@ -38,11 +77,34 @@ impl Config {
     }
 }
 
+/// What the CLI may do in a trusted directory
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+enum Scope {
+    /// Only run WebAssembly solutions, which are sandboxed
+    Sandbox,
+    /// Import and run solutions
+    Import,
+    /// Also submit answers
+    Submit,
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sandbox => write!(f, "sandbox"),
+            Self::Import => write!(f, "import"),
+            Self::Submit => write!(f, "submit"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Day {
     year: u16,
     day: u8,
     input: Option<String>,
+    #[serde(default)]
+    puzzle: Option<Puzzle>,
     part1: Part,
     part2: Part,
 }
@@ -53,6 +115,7 @@ impl Day {
             year,
             day,
             input: None,
+            puzzle: None,
             part1: Part::default(),
             part2: Part::default(),
         }
@@ -102,43 +165,129 @@ struct Args {
     command: Command,
 }
 
+#[derive(Debug, clap::Args)]
+struct RunArgs {
+    /// Only run solutions for the given year, or `today`, `latest` or an offset from the current day like `-1` for a single day
+    #[clap(allow_negative_numbers = true)]
+    year: Option<When>,
+    /// Only run solutions for the given day
+    day: Option<u8>,
+    /// Only run the given part of the solution
+    part: Option<u8>,
+    /// Submit solutions
+    #[clap(long)]
+    submit: bool,
+    /// Submit known incorrect solutions
+    #[clap(long)]
+    disable_submit_safety: bool,
+    /// Write everything the solutions print to this file
+    #[clap(long)]
+    log: Option<PathBuf>,
+    /// Start a post-mortem debugger when a solution raises an exception
+    #[clap(long)]
+    pdb: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct NewArgs {
+    /// The template to use for the new solution
+    template: String,
+    /// The path to create the new solution at, or the directory to create a template directory's files in
+    #[clap(allow_negative_numbers = true)]
+    file: Option<PathBuf>,
+    /// The year to create a new solution for, or `today`, `latest` or an offset from the current day like `+1`
+    #[clap(allow_negative_numbers = true)]
+    year: Option<When>,
+    /// The day to create a new solution for
+    day: Option<u8>,
+    /// The part to create a new solution for
+    part: Option<u8>,
+    /// Show what would be created without creating anything
+    #[clap(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum TemplateCommand {
+    /// Lists the available templates
+    List,
+    /// Shows a template's source
+    Show {
+        /// The template to show
+        name: String,
+    },
+    /// Copies a template into the project's templates directory, so it can be customised
+    Export {
+        /// The template to export
+        name: String,
+        /// The directory to export the template to instead
+        dir: Option<PathBuf>,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 enum Command {
     /// Trusts a directory to contain solutions
     Trust {
         /// The directory to trust
-        dir: PathBuf,
+        #[clap(required_unless_present = "list")]
+        dir: Option<PathBuf>,
+        /// What the CLI may do in the directory
+        #[clap(long, value_enum, default_value_t = Scope::Submit)]
+        scope: Scope,
+        /// Only import files once you've approved their contents, asking again whenever they change
+        #[clap(long)]
+        strict: bool,
+        /// Stop asking to approve files, forgetting the ones already approved
+        #[clap(long, conflicts_with = "strict")]
+        no_strict: bool,
+        /// List trusted directories instead
+        #[clap(long, conflicts_with_all = ["dir", "strict", "no_strict"])]
+        list: bool,
+    },
+    /// Stops trusting a directory
+    Untrust {
+        /// The directory to stop trusting
+        #[clap(required_unless_present = "missing")]
+        dir: Option<PathBuf>,
+        /// Stop trusting every directory that no longer exists instead
+        #[clap(long, conflicts_with = "dir")]
+        missing: bool,
     },
     /// Sets the session token to use for submitting solutions and fetching inputs
     Token,
     /// Runs and benchmarks all solutions
-    Run {
-        /// Only run solutions for the given year
-        year: Option<u16>,
-        /// Only run solutions for the given day
+    Run(RunArgs),
+    /// Creates a new solution from a template
+    New(NewArgs),
+    /// Waits for a puzzle to unlock, fetches it and creates a solution for it with the `template` setting
+    Start {
+        /// The year to start, or `today`, `latest` or an offset from the current day like `+1`, defaults to the next puzzle if it unlocks within the hour, otherwise today's
+        #[clap(allow_negative_numbers = true)]
+        year: Option<When>,
+        /// The day to start
         day: Option<u8>,
-        /// Only run the given part of the solution
-        part: Option<u8>,
-        /// Submit solutions
-        #[clap(long)]
-        submit: bool,
-        /// Submit known incorrect solutions
+    },
+    /// Manages the templates used by `aoc new`
+    Template {
+        #[clap(subcommand)]
+        command: TemplateCommand,
+    },
+    /// Submits an answer found without a solution, e.g. by hand or with another tool
+    Submit {
+        /// The year
+        year: u16,
+        /// The day
+        day: u8,
+        /// The part the answer is for
+        #[clap(value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: u8,
+        /// The answer
+        answer: String,
+        /// Submit known incorrect answers
         #[clap(long)]
         disable_submit_safety: bool,
     },
-    /// Creates a new solution from a template
-    New {
-        /// The template to use for the new solution
-        template: String,
-        /// The path to create the new solution at
-        file: PathBuf,
-        /// The year to create a new solution for
-        year: Option<u16>,
-        /// The day to create a new solution for
-        day: Option<u8>,
-        /// The part to create a new solution for
-        part: Option<u8>,
-    },
     /// Manually sets a solution
     SetSolution {
         /// The year
@@ -150,6 +299,18 @@ enum Command {
         /// The solution, leave blank to reset
         answer: Option<String>,
     },
+    /// Shows which Python interpreter, virtualenv and packages solutions will use
+    Doctor,
+    /// Re-runs solutions whenever their files change
+    Watch {
+        /// Only run solutions for the given year, or `today`, `latest` or an offset from the current day like `-1` for a single day
+        #[clap(allow_negative_numbers = true)]
+        year: Option<When>,
+        /// Only run solutions for the given day
+        day: Option<u8>,
+        /// Only run the given part of the solution
+        part: Option<u8>,
+    },
 }
 
 #[tokio::main]
@@ -165,38 +326,60 @@ async fn _main() -> Result<()> {
     let args = Args::parse();
     Logger::new(args.verbose.log_level_filter().as_trace()).init()?;
     let mut config: Config = confy::load(env!("CARGO_CRATE_NAME"), None)?;
-    let cwd = current_dir()?;
-    let Some(base_dir) = config.trusted_dirs.iter()
-        .find(|dir| cwd.starts_with(dir))
-    else {
-        if let Command::Trust { dir } = args.command {
-            commands::trust(&mut config, &dir)?;
-            confy::store(env!("CARGO_CRATE_NAME"), None, config)?;
-            return Ok(());
+    // Managing trust works from anywhere, as it doesn't run any solutions
+    let command = match args.command {
+        Command::Trust { list: true, .. } => {
+            commands::list_trusted(&mut config);
+            None
+        }
+        Command::Trust { dir: Some(dir), scope, strict, no_strict, .. } => {
+            let strict = match (strict, no_strict) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            commands::trust(&mut config, &dir, scope, strict)?;
+            None
         }
-        bail!("Current directory is not trusted. Use `aoc trust <dir>` to trust the current directory.");
+        Command::Untrust { dir, missing } => {
+            commands::untrust(&mut config, dir.as_deref(), missing)?;
+            None
+        }
+        command => Some(command),
+    };
+    let Some(command) = command else {
+        confy::store(env!("CARGO_CRATE_NAME"), None, config)?;
+        return Ok(());
+    };
+    let cwd = current_dir()?;
+    let Some(base_dir) = config.trusted_dir(&cwd).map(Path::to_path_buf) else {
+        bail!("Current directory is not trusted. Use `aoc trust <dir>` to trust the current directory, or `aoc trust --scope sandbox <dir>` to only run WebAssembly solutions.");
     };
-    if config.token.is_none() && !matches!(args.command, Command::Token) {
+    let scope = config.scope(&base_dir);
+    if config.token.is_none() && !matches!(command, Command::Token | Command::Doctor | Command::Template { .. }) {
         bail!("No token set. Use `aoc token` to set your session token.");
     }
-    match args.command {
-        Command::Trust { dir } => commands::trust(&mut config, &dir)?,
+    if scope < Scope::Submit && matches!(command, Command::Run(RunArgs { submit: true, .. }) | Command::Submit { .. }) {
+        bail!("{} is only trusted to {scope} solutions. Use `aoc trust --scope submit <dir>` to allow submitting.", base_dir.display());
+    }
+    // Paths in the global settings are relative to the config file, not each project
+    let config_path = confy::get_configuration_file_path(env!("CARGO_CRATE_NAME"), None)?;
+    let global = config.settings.clone().relative_to(config_path.parent().unwrap_or(Path::new("")));
+    let settings = project::settings(&global, &base_dir)?;
+    let settings = if scope == Scope::Sandbox { settings.sandboxed() } else { settings };
+    match command {
+        Command::Trust { .. } | Command::Untrust { .. } => unreachable!("Trust is managed before checking the current directory is trusted"),
         Command::Token => commands::token(&mut config)?,
-        Command::Run {
-            year,
-            day,
-            part,
-            submit,
-            disable_submit_safety
-        } => commands::run(&mut config, year, day, part, submit, disable_submit_safety).await?,
-        Command::New {
-            template,
-            file,
-            year,
-            day,
-            part
-        } => commands::new(base_dir, template, &file, year, day, part).await?,
+        Command::Run(args) => commands::run(&mut config, &base_dir, &settings, &args).await?,
+        Command::New(args) => {
+            commands::new(&mut config, &base_dir, &settings, args).await?;
+        }
+        Command::Start { year, day } => commands::start(&mut config, &base_dir, &settings, year, day).await?,
+        Command::Template { command } => commands::template(&base_dir, &settings, command).await?,
+        Command::Submit { year, day, part, answer, disable_submit_safety } => commands::submit(&mut config, &settings, year, day, part, &answer, disable_submit_safety).await?,
         Command::SetSolution {year, day, part, answer} => commands::set_solution(&mut config, year, day, part, answer),
+        Command::Doctor => commands::doctor(&mut config, &base_dir, &settings, scope)?,
+        Command::Watch {year, day, part} => commands::watch(&mut config, &base_dir, &settings, year, day, part).await?,
     }
     confy::store(env!("CARGO_CRATE_NAME"), None, config)?;
     Ok(())
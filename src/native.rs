@@ -0,0 +1,246 @@
+//! Solutions written in Rust with the `aoc-solutions` crate
+//!
+//! Crates depending on it are built, and their binaries calling `aoc::main` list their solutions with `<binary> list`,
+//! and run one with `<binary> run <name> <answers file>`, taking the input on stdin.
+
+use std::any::Any;
+use std::collections::HashSet;
+use std::env::{current_dir, temp_dir};
+use std::fs::{read, read_to_string, remove_file};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use tracing::{debug, error, warn};
+use crate::display::{ActionType, println};
+use crate::runner::{Runner, wait_with_timeout};
+use crate::solutions::{Failure, Implementation, Location, Outcome, register, Solution};
+
+const CRATE: &str = "aoc-solutions";
+/// Embedded in binaries that call `aoc::main`, must match `aoc::MARKER`
+const MARKER: &[u8] = b"aoc-solutions: lists solutions with `list`";
+
+#[derive(Debug, Clone)]
+pub struct NativeSolution {
+    executable: PathBuf,
+    /// The path of the function within its crate, which identifies it to the binary
+    name: String,
+}
+
+impl Implementation for NativeSolution {
+    /// Runs the solution, failing with the message it panicked with
+    fn run(&self, input: &str, timeout: Option<Duration>) -> Result<Outcome> {
+        static COUNT: AtomicU32 = AtomicU32::new(0);
+        let answers_file = temp_dir().join(format!("aoc-answers-{}-{}", std::process::id(), COUNT.fetch_add(1, Ordering::Relaxed)));
+        let mut child = Command::new(&self.executable)
+            .arg("run")
+            .arg(&self.name)
+            .arg(&answers_file)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start {}", self.executable.display()))?;
+        child.stdin.take().unwrap().write_all(input.as_bytes())?;
+        let Some(result) = wait_with_timeout(child, timeout)? else {
+            remove_file(&answers_file).ok();
+            return Ok((Err(Failure::timed_out(timeout.unwrap_or_default())), String::new()));
+        };
+        let stdout = String::from_utf8_lossy(&result.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&result.stderr).into_owned();
+        if !result.status.success() {
+            remove_file(&answers_file).ok();
+            let message = match stderr.trim() {
+                "" => format!("{} exited with {}", self.name, result.status),
+                stderr => stderr.to_string(),
+            };
+            return Ok((Err(Failure::new(message)), stdout));
+        }
+        let answers = read_to_string(&answers_file).context("The solution didn't write any answers")?;
+        remove_file(&answers_file)?;
+        let answers = answers.lines()
+            .map(|line| {
+                let (part, answer) = line.split_once('\t').ok_or_else(|| anyhow!("Invalid answer: {line}"))?;
+                Ok((part.parse()?, answer.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        Ok((Ok(answers), stdout + &stderr))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Whether a manifest is for a crate that depends on `aoc-solutions`
+fn depends_on_crate(manifest: &Path) -> Result<bool> {
+    let contents: toml::Table = toml::from_str(&read_to_string(manifest)?)
+        .with_context(|| format!("Failed to parse {}", manifest.display()))?;
+    Ok(contents.get("dependencies")
+        .and_then(toml::Value::as_table)
+        .is_some_and(|dependencies| dependencies.iter().any(|(name, dependency)| {
+            name == CRATE || dependency.get("package").and_then(toml::Value::as_str) == Some(CRATE)
+        })))
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(Deserialize)]
+struct Dependency {
+    /// The name of the package depended on, even if it's renamed
+    name: String,
+}
+
+/// The IDs of the packages in a crate's workspace that depend on `aoc-solutions`
+fn solution_packages(manifest: &Path) -> Result<HashSet<String>> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version=1", "--manifest-path"])
+        .arg(manifest)
+        .output()
+        .context("Failed to run cargo")?;
+    if !output.status.success() {
+        bail!("Failed to read the metadata of {}\n\n{}", manifest.display(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let metadata: Metadata = serde_json::from_slice(&output.stdout)?;
+    Ok(metadata.packages.into_iter()
+        .filter(|package| package.dependencies.iter().any(|dependency| dependency.name == CRATE))
+        .map(|package| package.id)
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct Artifact {
+    reason: String,
+    #[serde(default)]
+    package_id: String,
+    executable: Option<PathBuf>,
+}
+
+/// Builds a crate in release mode, returning the binaries of the packages that depend on `aoc-solutions`,
+/// as any others don't know how to list their solutions
+fn build(manifest: &Path) -> Result<Vec<PathBuf>> {
+    let packages = solution_packages(manifest)?;
+    let output = Command::new("cargo")
+        .args(["build", "--release", "--message-format=json-render-diagnostics", "--manifest-path"])
+        .arg(manifest)
+        .output()
+        .context("Failed to run cargo")?;
+    if !output.status.success() {
+        bail!("Failed to build {}\n\n{}", manifest.display(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    String::from_utf8_lossy(&output.stdout).lines()
+        .map(serde_json::from_str::<Artifact>)
+        .filter_map(|artifact| match artifact {
+            Ok(Artifact { reason, package_id, executable: Some(executable) })
+                if reason == "compiler-artifact" && packages.contains(&package_id) => Some(Ok(executable)),
+            Ok(_) => None,
+            Err(error) => Some(Err(error.into())),
+        })
+        .collect()
+}
+
+/// Whether a binary calls `aoc::main`, so it can be asked for its solutions
+fn is_solution_binary(executable: &Path) -> Result<bool> {
+    Ok(read(executable)?.windows(MARKER.len()).any(|window| window == MARKER))
+}
+
+/// Registers the solutions in a binary, returning the number that failed to register,
+/// `manifest_dir` is used to find the files they were defined in
+fn load(executable: &Path, manifest_dir: &Path) -> Result<u16> {
+    let output = Command::new(executable).arg("list").output()?;
+    if !output.status.success() {
+        bail!("Failed to list the solutions in {}\n\n{}", executable.display(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let mut failures = 0u16;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let [year, day, part, alternative, line_number, file, name] = line.split('\t').collect::<Vec<_>>()[..] else {
+            bail!("{} listed an invalid solution: {line}", executable.display());
+        };
+        // Paths are relative to the workspace the crate was built in, which may be above the crate
+        let file = manifest_dir.ancestors()
+            .map(|dir| dir.join(file))
+            .find(|file| file.exists())
+            .and_then(|file| file.canonicalize().ok());
+        let line = line_number.parse()?;
+        let solution = Solution {
+            year: year.parse()?,
+            day: day.parse()?,
+            part: Some(part.parse()?).filter(|part| *part != 0),
+            name: name.to_string(),
+            location: file.map(|file| Location { file, line: Some(line) }),
+            implementation: Arc::new(NativeSolution {
+                executable: executable.to_path_buf(),
+                name: name.to_string(),
+            }),
+        };
+        if let Err(error) = register(solution, alternative == "true") {
+            error!("{error}, use `#[solution(..., alternative)]` to register another implementation of it");
+            failures += 1;
+        }
+    }
+    Ok(failures)
+}
+
+/// Builds crates depending on `aoc-solutions` into binaries which it asks for their solutions
+pub struct NativeRunner;
+
+impl Runner for NativeRunner {
+    fn name(&self) -> &'static str {
+        "Rust"
+    }
+
+    fn handles(&self, file: &Path) -> bool {
+        file.file_name().is_some_and(|name| name == "Cargo.toml")
+            && !file.components().any(|component| component == Component::Normal("target".as_ref()))
+            && depends_on_crate(file).unwrap_or_else(|error| {
+                warn!("{error:#}");
+                false
+            })
+    }
+
+    /// Builds can run files outside the crate, e.g. `.cargo/config.toml` in a parent directory,
+    /// path dependencies and anything a build script reads, so crates aren't loaded in strict mode
+    fn sources(&self, _manifest: &Path) -> Result<Vec<PathBuf>> {
+        bail!("Rust crates can run files outside the crate while building, so they can't be checked in strict mode")
+    }
+
+    /// Builds the crate and registers the solutions in each of its binaries
+    fn load(&self, manifest: &Path) -> Result<u16> {
+        let manifest_dir = current_dir()?.join(manifest).parent().unwrap().to_path_buf();
+        let mut failures = 0;
+        for executable in build(manifest)? {
+            if !is_solution_binary(&executable)? {
+                debug!("Skipping {}, it doesn't call `aoc::main`", executable.display());
+                continue;
+            }
+            debug!("Loading solutions from {}", executable.display());
+            // Packages can have binaries that aren't solutions, which shouldn't stop the rest loading
+            match load(&executable, &manifest_dir) {
+                Ok(count) => failures += count,
+                Err(error) => warn!("Skipped {}: {error}", executable.display()),
+            }
+        }
+        Ok(failures)
+    }
+
+    fn describe(&self) -> Result<()> {
+        match Command::new("cargo").arg("--version").output() {
+            Ok(output) if output.status.success() => println("Cargo", ActionType::Info, String::from_utf8_lossy(&output.stdout).trim()),
+            _ => println("Cargo", ActionType::Warning, "not found, Rust solutions can't be built"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+//! Settings for a project, stored in `aoc.toml` in the trusted directory
+//!
+//! The same settings can be set globally in the config file, and those in `aoc.toml` override them,
+//! so teams can commit shared settings alongside their solutions.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::read_to_string;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use toml::Spanned;
+use tracing::warn;
+use crate::external::ExternalSolution;
+use crate::solutions::Location;
+use crate::templates;
+
+pub const FILE: &str = "aoc.toml";
+
+/// Whether answers are submitted when running with `--submit`
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmitPolicy {
+    /// Ask before submitting each answer
+    #[default]
+    Ask,
+    /// Submit without asking
+    Always,
+    /// Never submit
+    Never,
+}
+
+/// Settings that can be set globally or per project, unset settings use the defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Directories solutions are loaded from, relative to the trusted directory, instead of everything under it
+    pub roots: Option<Vec<PathBuf>>,
+    /// Where `aoc new` finds templates, relative to the trusted directory
+    pub templates: Option<PathBuf>,
+    /// Where templates are found when they aren't in the project's templates directory, e.g. a directory shared by a team,
+    /// relative to the file it's set in
+    pub shared_templates: Option<PathBuf>,
+    /// The template `aoc start` creates solutions with
+    pub template: Option<String>,
+    /// Where `aoc new` creates single file templates, as a template relative to the trusted directory
+    pub path: Option<String>,
+    /// The year used when one isn't given
+    pub year: Option<u16>,
+    /// The runners to load solutions with, by name, instead of all of them
+    pub runners: Option<Vec<String>>,
+    /// How many seconds a solution can run for before it's stopped
+    pub timeout: Option<f64>,
+    pub submit: Option<SubmitPolicy>,
+}
+
+impl Settings {
+    /// Overrides these settings with those set in `other`
+    #[must_use]
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            roots: other.roots.or(self.roots),
+            templates: other.templates.or(self.templates),
+            shared_templates: other.shared_templates.or(self.shared_templates),
+            template: other.template.or(self.template),
+            path: other.path.or(self.path),
+            year: other.year.or(self.year),
+            runners: other.runners.or(self.runners),
+            timeout: other.timeout.or(self.timeout),
+            submit: other.submit.or(self.submit),
+        }
+    }
+
+    /// The directories to load solutions from, or `None` to load everything under the current directory
+    pub fn roots(&self, base_dir: &Path) -> Option<Vec<PathBuf>> {
+        self.roots.as_ref().map(|roots| roots.iter().map(|root| base_dir.join(root)).collect())
+    }
+
+    pub fn templates(&self, base_dir: &Path) -> PathBuf {
+        base_dir.join(self.templates.as_deref().unwrap_or("templates".as_ref()))
+    }
+
+    pub fn template(&self) -> &str {
+        self.template.as_deref().unwrap_or("py")
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or(templates::DEFAULT_PATH)
+    }
+
+    /// Whether the runner called `name` is enabled
+    /// Only enables the sandboxed WebAssembly runner, whatever `aoc.toml` says
+    pub fn sandboxed(self) -> Self {
+        Self {
+            runners: Some(vec!["WebAssembly".to_string()]),
+            ..self
+        }
+    }
+
+    /// Resolves the paths in these settings against `dir`, the directory of the file they were set in
+    #[must_use]
+    pub fn relative_to(self, dir: &Path) -> Self {
+        Self {
+            shared_templates: self.shared_templates.map(|path| resolve(dir, &path)),
+            ..self
+        }
+    }
+
+    pub fn runner_enabled(&self, name: &str) -> bool {
+        self.runners.as_ref().is_none_or(|runners| runners.iter().any(|runner| runner.eq_ignore_ascii_case(name)))
+    }
+
+    pub fn timeout(&self) -> Result<Option<Duration>> {
+        self.timeout.map(|timeout| Duration::try_from_secs_f64(timeout)
+            .with_context(|| format!("Invalid timeout: {timeout}, expected a number of seconds")))
+            .transpose()
+    }
+
+    pub fn submit(&self) -> SubmitPolicy {
+        self.submit.unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Project {
+    /// Solutions run as commands
+    #[serde(default)]
+    pub solutions: Vec<Spanned<ExternalSolution>>,
+    #[serde(flatten)]
+    pub settings: Settings,
+    /// Anything else, which is most likely a typo
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    source: String,
+}
+
+impl Project {
+    /// Loads the project in `base_dir`, which has the default settings if it has no `aoc.toml`
+    pub fn load(base_dir: &Path) -> Result<Self> {
+        let path = base_dir.join(FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let source = read_to_string(&path)?;
+        let project = toml::from_str(&source).with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Self {
+            path,
+            source,
+            ..project
+        })
+    }
+
+    /// Where a value in `aoc.toml` was defined
+    pub fn location(&self, span: &Range<usize>) -> Location {
+        let line = self.source[..span.start].matches('\n').count() + 1;
+        Location {
+            file: self.path.clone(),
+            line: u32::try_from(line).ok(),
+        }
+    }
+}
+
+/// Combines the global settings with the project's in `base_dir`
+pub fn settings(global: &Settings, base_dir: &Path) -> Result<Settings> {
+    let project = Project::load(base_dir)?;
+    for key in project.unknown.keys() {
+        warn!("Unknown setting `{key}` in {}", project.path.display());
+    }
+    let settings = global.clone().merge(project.settings.relative_to(base_dir));
+    settings.timeout()?;
+    if let Some(year) = settings.year.filter(|year| *year < 2015) {
+        bail!("Invalid year: {year}, Advent of Code started in 2015");
+    }
+    Ok(settings)
+}
+
+/// Joins `path` to `dir`, expanding a leading `~` to the home directory
+fn resolve(dir: &Path, path: &Path) -> PathBuf {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+    match (path.strip_prefix("~"), home) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => dir.join(path),
+    }
+}
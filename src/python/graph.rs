@@ -0,0 +1,165 @@
+// pyo3 0.20's `#[pymethods]` expands to impls nested in its generated slots
+#![allow(non_local_definitions)]
+
+use std::collections::HashMap;
+use pyo3::{pyclass, pymethods, pymodule, PyObject, PyResult, Python};
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::PyModule;
+use pyo3::types::PyAny;
+use super::search::Hashable;
+
+/// A disjoint set of hashable items, items are added the first time they are seen
+#[pyclass(module = "aoc.graph")]
+#[derive(Debug, Default)]
+struct UnionFind {
+    indices: HashMap<Hashable, usize>,
+    items: Vec<PyObject>,
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+    count: usize,
+}
+
+impl UnionFind {
+    fn index(&mut self, item: &PyAny) -> PyResult<usize> {
+        let key = Hashable::new(item)?;
+        if let Some(index) = self.indices.get(&key) {
+            return Ok(*index);
+        }
+        let index = self.items.len();
+        self.indices.insert(key, index);
+        self.items.push(item.into());
+        self.parents.push(index);
+        self.sizes.push(1);
+        self.count += 1;
+        Ok(index)
+    }
+
+    fn root(&mut self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+        index
+    }
+}
+
+#[pymethods]
+impl UnionFind {
+    #[new]
+    #[pyo3(signature = (items = None))]
+    fn new(items: Option<&PyAny>) -> PyResult<Self> {
+        let mut union_find = Self::default();
+        if let Some(items) = items {
+            for item in items.iter()? {
+                union_find.index(item?)?;
+            }
+        }
+        Ok(union_find)
+    }
+
+    /// Adds `item` as its own set if it hasn't been seen yet
+    fn add(&mut self, item: &PyAny) -> PyResult<()> {
+        self.index(item)?;
+        Ok(())
+    }
+
+    /// Returns the representative item of the set containing `item`
+    fn find(&mut self, py: Python, item: &PyAny) -> PyResult<PyObject> {
+        let index = self.index(item)?;
+        let root = self.root(index);
+        Ok(self.items[root].clone_ref(py))
+    }
+
+    /// Merges the sets containing `a` and `b`, returning whether they were separate
+    fn union(&mut self, a: &PyAny, b: &PyAny) -> PyResult<bool> {
+        let a = self.index(a)?;
+        let b = self.index(b)?;
+        let (a, b) = (self.root(a), self.root(b));
+        if a == b {
+            return Ok(false);
+        }
+        let (big, small) = if self.sizes[a] >= self.sizes[b] { (a, b) } else { (b, a) };
+        self.parents[small] = big;
+        self.sizes[big] += self.sizes[small];
+        self.count -= 1;
+        Ok(true)
+    }
+
+    /// Returns whether `a` and `b` are in the same set
+    fn connected(&mut self, a: &PyAny, b: &PyAny) -> PyResult<bool> {
+        let a = self.index(a)?;
+        let b = self.index(b)?;
+        Ok(self.root(a) == self.root(b))
+    }
+
+    /// Returns the size of the set containing `item`
+    fn size(&mut self, item: &PyAny) -> PyResult<usize> {
+        let key = Hashable::new(item)?;
+        let index = *self.indices.get(&key)
+            .ok_or_else(|| PyKeyError::new_err(key.object))?;
+        let root = self.root(index);
+        Ok(self.sizes[root])
+    }
+
+    /// Returns every set as a list of items
+    fn groups(&mut self, py: Python) -> Vec<Vec<PyObject>> {
+        let mut groups: HashMap<usize, Vec<PyObject>> = HashMap::new();
+        for index in 0..self.items.len() {
+            let root = self.root(index);
+            groups.entry(root).or_default().push(self.items[index].clone_ref(py));
+        }
+        groups.into_values().collect()
+    }
+
+    /// The number of disjoint sets
+    #[getter]
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn __len__(&self) -> usize {
+        self.items.len()
+    }
+
+    fn __contains__(&self, item: &PyAny) -> PyResult<bool> {
+        Ok(self.indices.contains_key(&Hashable::new(item)?))
+    }
+}
+
+#[pymodule]
+pub fn graph(_py: Python, m: &PyModule) -> PyResult<()> {
+    submodules! {
+        module: m;
+        classes {
+            UnionFind,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prepare_freethreaded_python;
+    use super::*;
+
+    #[test]
+    fn test_union_find() {
+        prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let item = |code: &str| py.eval(code, None, None).unwrap();
+            let mut sets = UnionFind::new(Some(item("range(5)"))).unwrap();
+            assert_eq!(sets.count(), 5);
+            assert!(sets.union(item("0"), item("1")).unwrap());
+            assert!(sets.union(item("1"), item("2")).unwrap());
+            assert!(!sets.union(item("0"), item("2")).unwrap());
+            assert!(sets.union(item("5"), item("6")).unwrap());
+            assert_eq!(sets.count(), 4);
+            assert_eq!(sets.__len__(), 7);
+            assert_eq!(sets.size(item("2")).unwrap(), 3);
+            assert!(sets.connected(item("0"), item("2")).unwrap());
+            assert!(!sets.connected(item("0"), item("3")).unwrap());
+            assert!(sets.size(item("7")).is_err());
+        });
+    }
+}
@@ -0,0 +1,87 @@
+use pyo3::{pyfunction, pymodule, PyResult, Python};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::PyModule;
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g == gcd(a, b)`
+#[pyfunction]
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a.abs(), a.signum(), 0);
+    }
+    let (gcd, x, y) = extended_gcd(b, a.rem_euclid(b));
+    (gcd, y, x - a.div_euclid(b) * y)
+}
+
+/// Returns `x` such that `a * x % m == 1`
+#[pyfunction]
+fn mod_inverse(a: i128, m: i128) -> PyResult<i128> {
+    let (gcd, x, _) = extended_gcd(a, m);
+    if gcd != 1 {
+        return Err(PyValueError::new_err(format!("{a} has no inverse modulo {m}")));
+    }
+    Ok(x.rem_euclid(m))
+}
+
+/// Returns `(x, m)` where `x` is the smallest non-negative solution to `x % moduli[i] == remainders[i]` for all `i`,
+/// and all solutions are `x + k * m`, or `None` if there are none
+///
+/// The moduli don't need to be coprime.
+#[pyfunction]
+fn crt(remainders: Vec<i128>, moduli: Vec<i128>) -> PyResult<Option<(i128, i128)>> {
+    if remainders.len() != moduli.len() {
+        return Err(PyValueError::new_err("remainders and moduli must be the same length"));
+    }
+    if moduli.iter().any(|m| *m <= 0) {
+        return Err(PyValueError::new_err("moduli must be positive"));
+    }
+    let mut result = (0, 1);
+    for (remainder, modulus) in remainders.into_iter().zip(moduli) {
+        let (solution, period) = result;
+        let (gcd, inverse, _) = extended_gcd(period, modulus);
+        if (remainder - solution) % gcd != 0 {
+            return Ok(None);
+        }
+        let lcm = period / gcd * modulus;
+        let steps = ((remainder - solution) / gcd * inverse).rem_euclid(modulus / gcd);
+        result = ((solution + period * steps).rem_euclid(lcm), lcm);
+    }
+    Ok(Some(result))
+}
+
+#[pymodule]
+pub fn math(_py: Python, m: &PyModule) -> PyResult<()> {
+    submodules! {
+        module: m;
+        functions {
+            extended_gcd,
+            mod_inverse,
+            crt,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended_gcd() {
+        assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+        assert_eq!(extended_gcd(7, 0), (7, 1, 0));
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11).unwrap(), 4);
+        assert_eq!(mod_inverse(-3, 11).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(vec![2, 3, 2], vec![3, 5, 7]).unwrap(), Some((23, 105)));
+        assert_eq!(crt(vec![1, 3], vec![4, 6]).unwrap(), Some((9, 12)));
+        assert_eq!(crt(vec![0, 1], vec![4, 6]).unwrap(), None);
+    }
+}
@@ -1,7 +1,9 @@
-use pyo3::{pymodule, PyResult, Python};
+use std::path::Path;
+use anyhow::Result;
+use pyo3::{append_to_inittab, prepare_freethreaded_python, pymodule, PyErr, PyResult, Python};
 use pyo3::prelude::PyModule;
-
-pub mod solutions;
+use crate::integrity::Approvals;
+use crate::python::venv::VirtualEnv;
 
 macro_rules! submodules {
     {
@@ -12,6 +14,11 @@ macro_rules! submodules {
         }
         )?
         $(
+        classes {
+            $($class:path),*$(,)?
+        }
+        )?
+        $(
         submodules {
             $($module:path),*$(,)?
         }
@@ -24,22 +31,79 @@ macro_rules! submodules {
         )?
         $(
             $(
-                let submodule = pyo3::wrap_pymodule!($module);
-                $m.add_wrapped(submodule)?;
-                // TODO: Make importing work properly
+                $m.add_class::<$class>()?;
+            )*
+        )?
+        $(
+            $(
+                // Registering the submodule in `sys.modules` under its qualified name
+                // lets `import aoc.x` and `from aoc.x import y` find it
+                let submodule = pyo3::wrap_pymodule!($module)($m.py());
+                let name: String = submodule.getattr($m.py(), "__name__")?.extract($m.py())?;
+                let qualified_name = format!("{}.{name}", $m.name()?);
+                submodule.setattr($m.py(), "__name__", &qualified_name)?;
+                $m.py().import("sys")?.getattr("modules")?.set_item(&qualified_name, &submodule)?;
+                $m.add(&name, submodule)?;
             )*
         )?
     };
 }
 
+pub mod runner;
+pub mod solutions;
+pub mod venv;
+mod parse;
+mod search;
+mod graph;
+mod math;
+
 #[pymodule]
 pub fn aoc(_py: Python, m: &PyModule) -> PyResult<()> {
     submodules! {
         module: m;
         functions {
             solutions::solution,
+            parse::ints,
+            parse::lines,
+            parse::blocks,
+            parse::grid,
+        }
+        classes {
+            parse::Grid,
+        }
+        submodules {
+            search::search,
+            graph::graph,
+            math::math,
         }
     }
 
     Ok(())
 }
+
+/// Starts the embedded interpreter with the `aoc` module available and the project's virtualenv activated
+pub fn init(base_dir: &Path, approvals: Option<&mut Approvals>) -> Result<Option<VirtualEnv>> {
+    append_to_inittab!(aoc);
+    prepare_freethreaded_python();
+    Python::with_gil(|py| venv::activate(py, base_dir, approvals))
+}
+
+/// Runs `f` with `sys.stdout` and `sys.stderr` redirected to a buffer, returning its result and everything written to them
+pub fn capture_output<T>(py: Python, f: impl FnOnce() -> T) -> PyResult<(T, String)> {
+    let sys = py.import("sys")?;
+    let buffer = py.import("io")?.call_method0("StringIO")?;
+    let stdout = sys.getattr("stdout")?;
+    let stderr = sys.getattr("stderr")?;
+    sys.setattr("stdout", buffer)?;
+    sys.setattr("stderr", buffer)?;
+    let result = f();
+    sys.setattr("stdout", stdout)?;
+    sys.setattr("stderr", stderr)?;
+    Ok((result, buffer.call_method0("getvalue")?.extract()?))
+}
+
+/// Starts an interactive post-mortem `pdb` session on the traceback of `error`
+pub fn post_mortem(py: Python, error: &PyErr) -> PyResult<()> {
+    py.import("pdb")?.call_method1("post_mortem", (error.traceback(py),))?;
+    Ok(())
+}
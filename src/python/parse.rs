@@ -0,0 +1,246 @@
+// pyo3 0.20's `#[pymethods]` expands to impls nested in its generated slots
+#![allow(non_local_definitions)]
+
+use pyo3::{pyclass, pyfunction, pymethods, IntoPy, PyObject, PyResult, Python};
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::types::PyLong;
+
+/// Extracts the text of all integers in a string
+///
+/// A `-` is only treated as a sign if it doesn't follow a letter or digit, so ranges like `1-3` are positive
+fn extract_ints(s: &str) -> Vec<&str> {
+    let mut ints = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    let mut prev = None;
+    while let Some((start, c)) = chars.next() {
+        let negative = c == '-'
+            && !prev.is_some_and(char::is_alphanumeric)
+            && chars.peek().is_some_and(|(_, next)| next.is_ascii_digit());
+        prev = Some(c);
+        if !negative && !c.is_ascii_digit() {
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        while let Some((i, next)) = chars.next_if(|(_, next)| next.is_ascii_digit()) {
+            end = i + 1;
+            prev = Some(next);
+        }
+        ints.push(&s[start..end]);
+    }
+    ints
+}
+
+/// Splits a string into groups separated by blank lines
+fn split_blocks(s: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut block = Vec::new();
+    for line in s.lines() {
+        if line.trim().is_empty() {
+            if !block.is_empty() {
+                blocks.push(block.join("\n"));
+                block.clear();
+            }
+        } else {
+            block.push(line);
+        }
+    }
+    if !block.is_empty() {
+        blocks.push(block.join("\n"));
+    }
+    blocks
+}
+
+/// Returns all integers in `s`, e.g. `ints("x=-3, y=12")` is `[-3, 12]`, however large they are
+#[pyfunction]
+pub(super) fn ints(py: Python, s: &str) -> PyResult<Vec<PyObject>> {
+    extract_ints(s).into_iter().map(|int| match int.parse::<i64>() {
+        Ok(int) => Ok(int.into_py(py)),
+        // Too large for an `i64`, but Python's ints have no limit
+        Err(_) => Ok(py.get_type::<PyLong>().call1((int,))?.into_py(py)),
+    }).collect()
+}
+
+/// Returns the lines of `s`, without a trailing empty line
+#[pyfunction]
+pub(super) fn lines(s: &str) -> Vec<String> {
+    s.lines().map(ToString::to_string).collect()
+}
+
+/// Returns the groups of lines in `s` separated by blank lines
+#[pyfunction]
+pub(super) fn blocks(s: &str) -> Vec<String> {
+    split_blocks(s)
+}
+
+/// Parses `s` into a 2D grid of characters
+#[pyfunction]
+pub(super) fn grid(s: &str) -> Grid {
+    Grid {
+        cells: s.lines().map(|line| line.chars().collect()).collect(),
+    }
+}
+
+const ORTHOGONAL: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const DIAGONAL: [(i64, i64); 4] = [(1, -1), (1, 1), (-1, 1), (-1, -1)];
+
+/// A 2D grid of characters indexed by `(x, y)`, with `(0, 0)` at the top left
+#[pyclass(module = "aoc")]
+#[derive(Debug, Clone)]
+pub struct Grid {
+    cells: Vec<Vec<char>>,
+}
+
+impl Grid {
+    fn index(&self, (x, y): (i64, i64)) -> Option<(usize, usize)> {
+        let x = usize::try_from(x).ok()?;
+        let y = usize::try_from(y).ok()?;
+        (x < self.cells.get(y)?.len()).then_some((x, y))
+    }
+
+    #[allow(clippy::cast_possible_wrap)] // Grids are never anywhere near that big
+    fn positions(&self) -> impl Iterator<Item = ((i64, i64), char)> + '_ {
+        self.cells.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, c)| ((x as i64, y as i64), *c))
+        })
+    }
+}
+
+#[pymethods]
+impl Grid {
+    #[getter]
+    fn width(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// The rows of the grid as strings
+    #[getter]
+    fn rows(&self) -> Vec<String> {
+        self.cells.iter().map(|row| row.iter().collect()).collect()
+    }
+
+    /// The columns of the grid as strings
+    #[getter]
+    fn columns(&self) -> Vec<String> {
+        (0..self.width())
+            .map(|x| self.cells.iter().filter_map(|row| row.get(x)).collect())
+            .collect()
+    }
+
+    /// Returns the character at `pos`, or `default` if it is outside the grid
+    #[pyo3(signature = (pos, default = None))]
+    pub(super) fn get(&self, pos: (i64, i64), default: Option<char>) -> Option<char> {
+        self.index(pos).map(|(x, y)| self.cells[y][x]).or(default)
+    }
+
+    /// Returns the in-bounds positions next to `pos`, including diagonals if `diagonal` is true
+    #[pyo3(signature = (pos, diagonal = false))]
+    pub(super) fn neighbors(&self, pos: (i64, i64), diagonal: bool) -> Vec<(i64, i64)> {
+        let (x, y) = pos;
+        ORTHOGONAL.iter()
+            .chain(if diagonal { DIAGONAL.iter() } else { [].iter() })
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter(|pos| self.index(*pos).is_some())
+            .collect()
+    }
+
+    /// Returns the position of the first occurrence of `c`, reading left to right, top to bottom
+    fn find(&self, c: char) -> Option<(i64, i64)> {
+        self.positions().find(|(_, cell)| *cell == c).map(|(pos, _)| pos)
+    }
+
+    /// Returns the positions of all occurrences of `c`
+    fn find_all(&self, c: char) -> Vec<(i64, i64)> {
+        self.positions().filter(|(_, cell)| *cell == c).map(|(pos, _)| pos).collect()
+    }
+
+    /// Returns every `((x, y), c)` pair in the grid
+    fn items(&self) -> Vec<((i64, i64), char)> {
+        self.positions().collect()
+    }
+
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    fn __getitem__(&self, pos: (i64, i64)) -> PyResult<char> {
+        self.index(pos)
+            .map(|(x, y)| self.cells[y][x])
+            .ok_or_else(|| PyIndexError::new_err(format!("{pos:?} is outside the grid")))
+    }
+
+    fn __setitem__(&mut self, pos: (i64, i64), value: &str) -> PyResult<()> {
+        let mut chars = value.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return Err(PyValueError::new_err(format!("Expected a single character, got {value:?}")));
+        };
+        let (x, y) = self.index(pos)
+            .ok_or_else(|| PyIndexError::new_err(format!("{pos:?} is outside the grid")))?;
+        self.cells[y][x] = c;
+        Ok(())
+    }
+
+    fn __contains__(&self, pos: (i64, i64)) -> bool {
+        self.index(pos).is_some()
+    }
+
+    fn __len__(&self) -> usize {
+        self.cells.iter().map(Vec::len).sum()
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.cells == other.cells
+    }
+
+    fn __str__(&self) -> String {
+        self.rows().join("\n")
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Grid {}x{}>", self.width(), self.height())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_ints() {
+        assert_eq!(extract_ints("x=-3, y=12"), vec!["-3", "12"]);
+        assert_eq!(extract_ints("1-3 a: 345"), vec!["1", "3", "345"]);
+        assert_eq!(extract_ints("move -1,-20"), vec!["-1", "-20"]);
+        assert_eq!(extract_ints("no numbers - here"), Vec::<&str>::new());
+        assert_eq!(extract_ints("-"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_large_ints() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let ints = ints(py, "5 -99999999999999999999 18446744073709551616").unwrap();
+            let ints: Vec<String> = ints.iter().map(|int| int.as_ref(py).str().unwrap().to_string()).collect();
+            assert_eq!(ints, ["5", "-99999999999999999999", "18446744073709551616"]);
+        });
+    }
+
+    #[test]
+    fn test_split_blocks() {
+        assert_eq!(split_blocks("a\nb\n\nc\n"), vec!["a\nb", "c"]);
+        assert_eq!(split_blocks("\n\na\r\n\r\n\r\nb"), vec!["a", "b"]);
+        assert_eq!(split_blocks(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_grid_neighbors() {
+        let grid = grid("ab\ncd\n");
+        assert_eq!(grid.neighbors((0, 0), false), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors((0, 0), true), vec![(1, 0), (0, 1), (1, 1)]);
+        assert_eq!(grid.find('d'), Some((1, 1)));
+        assert_eq!(grid.get((2, 0), None), None);
+    }
+}
@@ -0,0 +1,91 @@
+use std::env::current_dir;
+use std::fs::read_to_string;
+use std::path::{Component, Path, PathBuf};
+use anyhow::Result;
+use pyo3::Python;
+use pyo3::types::PyModule;
+use crate::display::{ActionType, FormatTraceBack, println};
+use crate::integrity::Approvals;
+use crate::python::venv::{project_paths, VirtualEnv};
+use crate::runner::{files_in, has_extension, Runner};
+
+/// Imports Python files into the embedded interpreter, where solutions register themselves with the `solution` decorator
+pub struct PythonRunner {
+    base_dir: PathBuf,
+    venv: Option<VirtualEnv>,
+}
+
+impl PythonRunner {
+    /// Starts the interpreter, activating the project's virtualenv once it's approved in strict mode
+    pub fn new(base_dir: &Path, approvals: Option<&mut Approvals>) -> Result<Self> {
+        Ok(Self {
+            base_dir: base_dir.to_path_buf(),
+            venv: super::init(base_dir, approvals)?,
+        })
+    }
+
+    fn in_venv(&self, file: &Path) -> bool {
+        self.venv.as_ref()
+            .zip(current_dir().ok())
+            .is_some_and(|(venv, cwd)| cwd.join(file).starts_with(&venv.path))
+    }
+}
+
+impl Runner for PythonRunner {
+    fn name(&self) -> &'static str {
+        "Python"
+    }
+
+    fn handles(&self, file: &Path) -> bool {
+        let hidden = file.components()
+            .any(|component| matches!(component, Component::Normal(name) if name.to_string_lossy().starts_with('.')));
+        has_extension(file, &["py"]) && !self.in_venv(file) && !hidden
+    }
+
+    /// The file itself and every Python file in the project's import paths, as it could import any of them
+    fn sources(&self, file: &Path) -> Result<Vec<PathBuf>> {
+        let cwd = current_dir()?;
+        let mut sources = vec![file.to_path_buf()];
+        for dir in project_paths(&self.base_dir) {
+            // Skips hidden directories inside the project, but not one the project itself is in
+            for source in files_in(&dir)? {
+                // Relative to the current directory where possible, like the files runners are given
+                let source = source.strip_prefix(&cwd).map(Path::to_path_buf).unwrap_or(source);
+                if has_extension(&source, &["py"]) && !self.in_venv(&source) && !sources.contains(&source) {
+                    sources.push(source);
+                }
+            }
+        }
+        Ok(sources)
+    }
+
+    /// Imports the file as its own module
+    fn load(&self, file: &Path) -> Result<u16> {
+        let module = format!("aoc_{}", file.display().to_string().replace(|c: char| !c.is_ascii_alphanumeric(), "_"));
+        let code = read_to_string(file)?;
+        Python::with_gil(|py| PyModule::from_code(py, &code, &file.display().to_string(), &module).map(|_| ())).tb()?;
+        Ok(0)
+    }
+
+    fn describe(&self) -> Result<()> {
+        match &self.venv {
+            Some(venv) => println("Virtualenv", ActionType::Success, format!("{} (from {})", venv.path.display(), venv.source)),
+            None => println("Virtualenv", ActionType::Warning, "none found, only the interpreter's own packages are available"),
+        }
+        Python::with_gil(|py| -> Result<()> {
+            let sys = py.import("sys")?;
+            let version: String = sys.getattr("version")?.extract()?;
+            println("Python", ActionType::Info, version.lines().next().unwrap_or_default());
+            println("Executable", ActionType::Info, sys.getattr("executable")?.extract::<String>()?);
+            println("Prefix", ActionType::Info, sys.getattr("prefix")?.extract::<String>()?);
+            let path: Vec<String> = sys.getattr("path")?.extract()?;
+            for entry in path.iter().filter(|entry| entry.ends_with("site-packages")) {
+                println("Packages", ActionType::Info, entry);
+            }
+            for entry in path.iter().filter(|entry| !entry.is_empty()) {
+                println("sys.path", ActionType::Debug, entry);
+            }
+            Ok(())
+        })
+    }
+}
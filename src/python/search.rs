@@ -0,0 +1,367 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::{Hash, Hasher};
+use pyo3::{IntoPy, pyfunction, pymodule, PyObject, PyRef, PyResult, Python};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::PyModule;
+use pyo3::types::{PyAny, PyDict};
+use super::parse::Grid;
+
+/// A Python object that can be used as a key in Rust collections, using its `__hash__` and `__eq__`
+#[derive(Debug, Clone)]
+pub(super) struct Hashable {
+    pub(super) object: PyObject,
+    hash: isize,
+}
+
+impl Hashable {
+    pub(super) fn new(object: &PyAny) -> PyResult<Self> {
+        Ok(Self {
+            hash: object.hash()?,
+            object: object.into(),
+        })
+    }
+}
+
+impl Hash for Hashable {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+impl PartialEq for Hashable {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && Python::with_gil(|py| {
+            self.object.as_ref(py).eq(other.object.as_ref(py)).unwrap_or(false)
+        })
+    }
+}
+
+impl Eq for Hashable {}
+
+type Predicate<'a, S> = Box<dyn Fn(&S) -> PyResult<bool> + 'a>;
+
+/// A graph that can be searched
+trait Space<'py> {
+    type State: Clone + Eq + Hash;
+
+    fn py(&self) -> Python<'py>;
+    fn state(&self, object: &'py PyAny) -> PyResult<Self::State>;
+    fn object(&self, state: &Self::State) -> PyObject;
+    fn neighbors(&self, state: &Self::State) -> PyResult<Vec<(Self::State, i64)>>;
+
+    /// Turns `goal` into a predicate, callables are called with each state, anything else is compared to each state
+    fn goal<'a>(&'a self, goal: &'py PyAny) -> PyResult<Predicate<'a, Self::State>> where 'py: 'a {
+        if goal.is_callable() {
+            Ok(Box::new(move |state| goal.call1((self.object(state),))?.is_true()))
+        } else {
+            let goal = self.state(goal)?;
+            Ok(Box::new(move |state| Ok(*state == goal)))
+        }
+    }
+}
+
+/// A graph defined by a Python callable returning the neighbours of a state, with costs if `weighted`
+struct Callback<'py> {
+    neighbors: &'py PyAny,
+    weighted: bool,
+}
+
+impl<'py> Space<'py> for Callback<'py> {
+    type State = Hashable;
+
+    fn py(&self) -> Python<'py> {
+        self.neighbors.py()
+    }
+
+    fn state(&self, object: &'py PyAny) -> PyResult<Self::State> {
+        Hashable::new(object)
+    }
+
+    fn object(&self, state: &Self::State) -> PyObject {
+        state.object.clone_ref(self.py())
+    }
+
+    fn neighbors(&self, state: &Self::State) -> PyResult<Vec<(Self::State, i64)>> {
+        self.neighbors.call1((self.object(state),))?
+            .iter()?
+            .map(|neighbor| {
+                let neighbor = neighbor?;
+                if self.weighted {
+                    let (neighbor, cost): (&PyAny, i64) = neighbor.extract()?;
+                    Ok((Hashable::new(neighbor)?, cost))
+                } else {
+                    Ok((Hashable::new(neighbor)?, 1))
+                }
+            })
+            .collect()
+    }
+}
+
+/// A [`Grid`] where every step between orthogonally adjacent cells not in `walls` costs 1
+struct GridSpace<'py> {
+    grid: PyRef<'py, Grid>,
+    walls: Vec<char>,
+}
+
+impl<'py> Space<'py> for GridSpace<'py> {
+    type State = (i64, i64);
+
+    fn py(&self) -> Python<'py> {
+        self.grid.py()
+    }
+
+    fn state(&self, object: &'py PyAny) -> PyResult<Self::State> {
+        object.extract()
+    }
+
+    fn object(&self, state: &Self::State) -> PyObject {
+        state.into_py(self.py())
+    }
+
+    fn neighbors(&self, state: &Self::State) -> PyResult<Vec<(Self::State, i64)>> {
+        Ok(self.grid.neighbors(*state, false).into_iter()
+            .filter(|pos| self.grid.get(*pos, None).is_some_and(|c| !self.walls.contains(&c)))
+            .map(|pos| (pos, 1))
+            .collect())
+    }
+
+    /// Also accepts a single character, matching any cell containing it
+    fn goal<'a>(&'a self, goal: &'py PyAny) -> PyResult<Predicate<'a, Self::State>> where 'py: 'a {
+        if let Ok(c) = goal.extract::<char>() {
+            return Ok(Box::new(move |state| Ok(self.grid.get(*state, None) == Some(c))));
+        }
+        if goal.is_callable() {
+            return Ok(Box::new(move |state| goal.call1((self.object(state),))?.is_true()));
+        }
+        let goal = self.state(goal)?;
+        Ok(Box::new(move |state| Ok(*state == goal)))
+    }
+}
+
+struct Entry<S> {
+    priority: i64,
+    cost: i64,
+    order: usize,
+    state: S,
+}
+
+impl<S> PartialEq for Entry<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<S> Eq for Entry<S> {}
+
+impl<S> PartialOrd for Entry<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> Ord for Entry<S> {
+    /// Reversed so [`BinaryHeap`] pops the lowest priority first, ties are broken by insertion order
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority)
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+/// Dijkstra's algorithm, or A* if `heuristic` is given
+///
+/// With a goal, returns the cost to the first state matching it (and the path if `path`), or `None` if it is unreachable.
+/// Without a goal, returns a dict of the cost to every reachable state.
+fn shortest_path<'py, S: Space<'py>>(space: &S, start: &'py PyAny, goal: Option<&'py PyAny>, heuristic: Option<&'py PyAny>, path: bool) -> PyResult<PyObject> {
+    let py = space.py();
+    let is_goal = goal.map(|goal| space.goal(goal)).transpose()?;
+    let estimate = |state: &S::State| -> PyResult<i64> {
+        heuristic.map_or(Ok(0), |heuristic| heuristic.call1((space.object(state),))?.extract())
+    };
+    let start = space.state(start)?;
+    let mut costs = HashMap::from([(start.clone(), 0)]);
+    let mut parents: HashMap<S::State, S::State> = HashMap::new();
+    let mut queue = BinaryHeap::from([Entry { priority: estimate(&start)?, cost: 0, order: 0, state: start }]);
+    let mut order = 0;
+    while let Some(Entry { cost, state, .. }) = queue.pop() {
+        if costs.get(&state).is_some_and(|best| cost > *best) {
+            continue;
+        }
+        if let Some(is_goal) = &is_goal {
+            if is_goal(&state)? {
+                if !path {
+                    return Ok(cost.into_py(py));
+                }
+                let mut states = vec![space.object(&state)];
+                let mut current = &state;
+                while let Some(parent) = parents.get(current) {
+                    states.push(space.object(parent));
+                    current = parent;
+                }
+                states.reverse();
+                return Ok((cost, states).into_py(py));
+            }
+        }
+        for (neighbor, step) in space.neighbors(&state)? {
+            let cost = cost + step;
+            if costs.get(&neighbor).is_some_and(|best| cost >= *best) {
+                continue;
+            }
+            costs.insert(neighbor.clone(), cost);
+            parents.insert(neighbor.clone(), state.clone());
+            order += 1;
+            queue.push(Entry { priority: cost + estimate(&neighbor)?, cost, order, state: neighbor });
+        }
+    }
+    if is_goal.is_some() {
+        return Ok(py.None());
+    }
+    let costs_dict = PyDict::new(py);
+    for (state, cost) in costs {
+        costs_dict.set_item(space.object(&state), cost)?;
+    }
+    Ok(costs_dict.into())
+}
+
+fn search_with<'py>(start: &'py PyAny, neighbors: &'py PyAny, goal: Option<&'py PyAny>, heuristic: Option<&'py PyAny>, walls: &str, weighted: bool, path: bool) -> PyResult<PyObject> {
+    if let Ok(grid) = neighbors.extract::<PyRef<Grid>>() {
+        shortest_path(&GridSpace { grid, walls: walls.chars().collect() }, start, goal, heuristic, path)
+    } else if neighbors.is_callable() {
+        shortest_path(&Callback { neighbors, weighted }, start, goal, heuristic, path)
+    } else {
+        Err(PyValueError::new_err("neighbors must be a callable or a Grid"))
+    }
+}
+
+/// Breadth first search from `start`
+///
+/// `neighbors` is either a callable returning the states reachable from a state, or a `Grid` where cells in `walls`
+/// can't be entered. `goal` is a state, a callable returning whether a state is the goal, or for grids a character.
+#[pyfunction]
+#[pyo3(signature = (start, neighbors, goal = None, *, walls = "#", path = false))]
+fn bfs<'py>(start: &'py PyAny, neighbors: &'py PyAny, goal: Option<&'py PyAny>, walls: &str, path: bool) -> PyResult<PyObject> {
+    search_with(start, neighbors, goal, None, walls, false, path)
+}
+
+/// Like `bfs`, but `neighbors` returns `(state, cost)` pairs
+#[pyfunction]
+#[pyo3(signature = (start, neighbors, goal = None, *, walls = "#", path = false))]
+fn dijkstra<'py>(start: &'py PyAny, neighbors: &'py PyAny, goal: Option<&'py PyAny>, walls: &str, path: bool) -> PyResult<PyObject> {
+    search_with(start, neighbors, goal, None, walls, true, path)
+}
+
+/// Like `dijkstra`, guided by `heuristic`, which must never overestimate the remaining cost from a state
+#[pyfunction]
+#[pyo3(signature = (start, neighbors, goal, heuristic, *, walls = "#", path = false))]
+fn astar<'py>(start: &'py PyAny, neighbors: &'py PyAny, goal: &'py PyAny, heuristic: &'py PyAny, walls: &str, path: bool) -> PyResult<PyObject> {
+    search_with(start, neighbors, Some(goal), Some(heuristic), walls, true, path)
+}
+
+/// Follows `step` from `start` until a state repeats or `limit` states have been seen
+///
+/// Returns the states seen and, if a state repeated, the index of the first repeated state.
+fn walk(start: &PyAny, step: &PyAny, limit: Option<usize>) -> PyResult<(Vec<PyObject>, Option<usize>)> {
+    let mut seen = HashMap::new();
+    let mut states = Vec::new();
+    let mut state = start;
+    while limit.is_none_or(|limit| states.len() < limit) {
+        let key = Hashable::new(state)?;
+        if let Some(index) = seen.get(&key) {
+            return Ok((states, Some(*index)));
+        }
+        seen.insert(key, states.len());
+        states.push(state.into());
+        state = step.call1((state,))?;
+    }
+    Ok((states, None))
+}
+
+/// Returns `(offset, length)` of the cycle reached by repeatedly calling `step` on `start`
+///
+/// The state after `offset` steps is the first one that repeats, and it repeats every `length` steps.
+#[pyfunction]
+fn find_cycle(start: &PyAny, step: &PyAny) -> PyResult<(usize, usize)> {
+    let (states, offset) = walk(start, step, None)?;
+    let offset = offset.expect("walk only stops without a limit once a state repeats");
+    Ok((offset, states.len() - offset))
+}
+
+/// Returns the state after calling `step` on `start` `n` times, skipping ahead once a cycle is found
+#[pyfunction]
+fn nth_state(start: &PyAny, step: &PyAny, n: usize) -> PyResult<PyObject> {
+    // `n` can't be reached without a cycle if it's too large to count up to anyway
+    let (mut states, offset) = walk(start, step, n.checked_add(1))?;
+    let index = match offset {
+        Some(offset) if n >= states.len() => offset + (n - offset) % (states.len() - offset),
+        _ => n,
+    };
+    Ok(states.swap_remove(index))
+}
+
+#[pymodule]
+pub fn search(_py: Python, m: &PyModule) -> PyResult<()> {
+    submodules! {
+        module: m;
+        functions {
+            bfs,
+            dijkstra,
+            astar,
+            find_cycle,
+            nth_state,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::{prepare_freethreaded_python, Py};
+    use super::*;
+    use super::super::parse::grid;
+
+    fn eval<'py>(py: Python<'py>, code: &str) -> &'py PyAny {
+        py.eval(code, None, None).unwrap()
+    }
+
+    #[test]
+    fn test_grid_bfs() {
+        prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let maze = Py::new(py, grid("S.#\n#.#\n..E\n")).unwrap();
+            let maze = maze.as_ref(py);
+            let cost: i64 = bfs(eval(py, "(0, 0)"), maze, Some(eval(py, "'E'")), "#", false).unwrap().extract(py).unwrap();
+            assert_eq!(cost, 4);
+            let walled = bfs(eval(py, "(0, 0)"), maze, Some(eval(py, "'E'")), "#.", false).unwrap();
+            assert!(walled.is_none(py));
+        });
+    }
+
+    #[test]
+    fn test_dijkstra_path() {
+        prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let neighbors = eval(py, "lambda s: {'a': [('b', 1), ('c', 5)], 'b': [('c', 1)], 'c': [], 'd': []}[s]");
+            let (cost, path): (i64, Vec<String>) = dijkstra(eval(py, "'a'"), neighbors, Some(eval(py, "'c'")), "#", true).unwrap().extract(py).unwrap();
+            assert_eq!(cost, 2);
+            assert_eq!(path, ["a", "b", "c"]);
+            let costs: HashMap<String, i64> = dijkstra(eval(py, "'a'"), neighbors, None, "#", false).unwrap().extract(py).unwrap();
+            assert_eq!(costs, HashMap::from([("a".to_string(), 0), ("b".to_string(), 1), ("c".to_string(), 2)]));
+        });
+    }
+
+    #[test]
+    fn test_nth_state() {
+        prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            // 5, 4, 3, then 2, 1, 0 repeating
+            let step = eval(py, "lambda x: x - 1 if x > 0 else 2");
+            let nth = |n| nth_state(eval(py, "5"), step, n).unwrap().extract::<i64>(py).unwrap();
+            assert_eq!(nth(0), 5);
+            assert_eq!(nth(4), 1);
+            assert_eq!(nth(100), 1);
+            assert_eq!(nth(usize::MAX), 2);
+            assert_eq!(find_cycle(eval(py, "5"), step).unwrap(), (3, 3));
+        });
+    }
+}
@@ -1,26 +1,157 @@
-use std::sync::RwLock;
-use pyo3::{Py, PyAny, pyfunction, PyResult, Python};
-use pyo3::exceptions::PyTypeError;
+use std::any::Any;
+use std::fs::canonicalize;
+use std::path::PathBuf;
+use std::os::raw::{c_long, c_ulong};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+use anyhow::Result;
+use pyo3::{ffi, Py, PyAny, pyfunction, PyResult, Python};
+use pyo3::exceptions::{PyTypeError, PyValueError};
 use pyo3::types::PyCFunction;
-use lazy_static::lazy_static;
+use crate::display::{FormatTraceBack, traceback_error};
+use crate::solutions::{Failure, Implementation, Location, Outcome, register, Solution, validate};
 
-#[derive(Debug, Clone)]
-pub struct Solution {
-    pub(crate) year: u16,
-    pub(crate) day: u8,
-    pub(crate) part: u8,
-    pub(crate) function: Py<PyAny>,
+/// A function registered with the `solution` decorator
+#[derive(Debug)]
+struct PythonSolution {
+    function: Py<PyAny>,
+    /// Called with the input before it is passed to `function`
+    parse: Option<Py<PyAny>>,
+    /// The part the function solves, or `None` if it returns `(part1, part2)`
+    part: Option<u8>,
+    name: String,
 }
 
-lazy_static! {
-    pub static ref SOLUTIONS: RwLock<Vec<Solution>> = RwLock::new(Vec::new());
+impl PythonSolution {
+    fn call(&self, py: Python, input: &str) -> PyResult<Vec<(u8, String)>> {
+        let result = match &self.parse {
+            Some(parse) => self.function.call1(py, (parse.call1(py, (input,))?,))?,
+            None => self.function.call1(py, (input,))?,
+        };
+        let result = result.as_ref(py);
+        let Some(part) = self.part else {
+            let (part1, part2): (&PyAny, &PyAny) = result.extract().map_err(|_| PyTypeError::new_err(format!(
+                "{} solves both parts, so it must return a (part1, part2) tuple, got {}",
+                self.name,
+                result,
+            )))?;
+            // A part that hasn't been solved yet can be left as `None`
+            return [(1, part1), (2, part2)].into_iter()
+                .filter(|(_, answer)| !answer.is_none())
+                .map(|(part, answer)| Ok((part, answer.str()?.to_string())))
+                .collect();
+        };
+        Ok(vec![(part, result.str()?.to_string())])
+    }
+}
+
+/// Raises `TimeoutError` in the thread that started it unless it's dropped before the timeout
+///
+/// The exception is only raised once the thread runs Python code, so solutions stuck in native code can't be stopped.
+struct Watchdog {
+    thread: c_long,
+    finished: Arc<AtomicBool>,
+    /// Dropped to stop the watchdog
+    _stop: Sender<()>,
+}
+
+impl Watchdog {
+    fn start(py: Python, timeout: Duration) -> PyResult<Self> {
+        let thread: c_ulong = py.import("threading")?.call_method0("get_ident")?.extract()?;
+        // Python takes the identifier as signed, but it's the same bits
+        #[allow(clippy::cast_possible_wrap)]
+        let thread = thread as c_long;
+        let finished = Arc::new(AtomicBool::new(false));
+        let (stop, stopped) = channel();
+        let watchdog_finished = finished.clone();
+        thread::spawn(move || {
+            if stopped.recv_timeout(timeout) != Err(RecvTimeoutError::Timeout) {
+                return;
+            }
+            // Checked while holding the GIL, so the solution can't finish between checking and raising
+            Python::with_gil(|_| {
+                if !watchdog_finished.load(Ordering::SeqCst) {
+                    unsafe {
+                        ffi::PyThreadState_SetAsyncExc(thread, ffi::PyExc_TimeoutError);
+                    }
+                }
+            });
+        });
+        Ok(Self {
+            thread,
+            finished,
+            _stop: stop,
+        })
+    }
+}
+
+impl Drop for Watchdog {
+    /// Must be dropped while holding the GIL
+    fn drop(&mut self) {
+        self.finished.store(true, Ordering::SeqCst);
+        // The exception may have been raised after the solution returned, before it could be raised in the solution
+        unsafe {
+            ffi::PyThreadState_SetAsyncExc(self.thread, std::ptr::null_mut());
+        }
+    }
+}
+
+impl Implementation for PythonSolution {
+    fn run(&self, input: &str, timeout: Option<Duration>) -> Result<Outcome> {
+        let (results, output) = Python::with_gil(|py| {
+            let _watchdog = timeout.map(|timeout| Watchdog::start(py, timeout)).transpose()?;
+            super::capture_output(py, || self.call(py, input))
+        }).tb()?;
+        let results = results.map_err(|error| {
+            let message = Python::with_gil(|py| traceback_error(error.clone_ref(py))).to_string();
+            Failure::new(message).with_debugger(move || Python::with_gil(|py| super::post_mortem(py, &error)).tb())
+        });
+        Ok((results, output))
+    }
+
+    fn is(&self, other: &dyn Implementation) -> bool {
+        other.as_any().downcast_ref::<Self>().is_some_and(|other| self.function.as_ptr() == other.function.as_ptr())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Where a function was defined, used to recognise the same function being registered again when its file is re-imported
+fn location(function: &PyAny) -> Option<Location> {
+    let code = function.getattr("__code__").ok()?;
+    let file: String = code.getattr("co_filename").ok()?.extract().ok()?;
+    // The CLI imports files by relative path, but `import` uses absolute paths
+    let file = canonicalize(&file).unwrap_or_else(|_| PathBuf::from(file));
+    Some(Location {
+        file,
+        line: Some(code.getattr("co_firstlineno").ok()?.extract().ok()?),
+    })
+}
+
+fn name(function: &PyAny) -> String {
+    function.getattr("__qualname__")
+        .or_else(|_| function.getattr("__name__"))
+        .and_then(PyAny::extract)
+        .unwrap_or_else(|_| function.to_string())
 }
 
 #[pyfunction]
-pub(super) fn solution(py: Python, year: u16, day: u8, part: u8) -> PyResult<&PyCFunction> {
-    if ![1, 2].contains(&part) {
-        return Err(PyTypeError::new_err("Invalid part number: {part}, expected 1 or 2"));
+#[pyo3(signature = (year, day, part = None, *, alternative = false, parse = None))]
+pub(super) fn solution<'py>(py: Python<'py>, year: u16, day: u8, part: Option<u8>, alternative: bool, parse: Option<&PyAny>) -> PyResult<&'py PyCFunction> {
+    validate(year, day, part).map_err(PyValueError::new_err)?;
+    if let Some(parse) = parse.filter(|parse| !parse.is_callable()) {
+        return Err(PyTypeError::new_err(format!("parse must be callable, got {parse}")));
     }
+    let parse: Option<Py<PyAny>> = parse.map(Into::into);
+    let description = match part {
+        Some(part) => format!("{year} day {day} part {part}"),
+        None => format!("{year} day {day}"),
+    };
 
    PyCFunction::new_closure(py, None, None, move |args, kwargs| {
        if kwargs.is_some() {
@@ -30,15 +161,32 @@ pub(super) fn solution(py: Python, year: u16, day: u8, part: u8) -> PyResult<&Py
            return Err(PyTypeError::new_err("Expected 1 positional argument"));
        }
 
-       let function = Py::from(args.get_item(0)?);
-       let mut solutions = SOLUTIONS.write().map_err(|_| PyTypeError::new_err("Failed to acquire write lock on SOLUTIONS"))?;
-       solutions.push(Solution {
+       let function = args.get_item(0)?;
+       if !function.is_callable() {
+           return Err(PyTypeError::new_err(format!("{description}: {function} is not callable")));
+       }
+       // Not all callables support attributes (e.g. builtins), they are only for introspection so failing is fine
+       function.setattr("__aoc_year__", year).ok();
+       function.setattr("__aoc_day__", day).ok();
+       function.setattr("__aoc_part__", part).ok();
+       let name = name(function);
+       let solution = Solution {
            year,
            day,
            part,
-           function,
-       });
+           name: name.clone(),
+           location: location(function),
+           implementation: Arc::new(PythonSolution {
+               function: function.into(),
+               parse: parse.as_ref().map(|parse| parse.clone_ref(function.py())),
+               part,
+               name,
+           }),
+       };
+       register(solution, alternative).map_err(|error| PyValueError::new_err(format!(
+           "{error}, use `alternative=True` to register another implementation of it",
+       )))?;
 
-       Ok(())
+       Ok(Py::<PyAny>::from(function))
    })
 }
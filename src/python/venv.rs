@@ -0,0 +1,168 @@
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use glob::glob;
+use pyo3::Python;
+use pyo3::types::PyList;
+use tracing::{debug, warn};
+use crate::integrity::{approve, Approvals};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VenvSource {
+    /// `VIRTUAL_ENV` is set, usually because the environment is activated
+    Activated,
+    /// Found as a directory in the trusted directory
+    Directory,
+}
+
+impl Display for VenvSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Activated => write!(f, "VIRTUAL_ENV"),
+            Self::Directory => write!(f, "trusted directory"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VirtualEnv {
+    pub path: PathBuf,
+    pub source: VenvSource,
+}
+
+impl VirtualEnv {
+    /// Finds the virtualenv to use, preferring an activated one over one in the trusted directory
+    pub fn find(base_dir: &Path) -> Option<Self> {
+        if let Some(path) = env::var_os("VIRTUAL_ENV") {
+            let path = PathBuf::from(path);
+            if path.join("pyvenv.cfg").exists() {
+                return Some(Self { path, source: VenvSource::Activated });
+            }
+            warn!("VIRTUAL_ENV is set to {}, but it is not a virtualenv", path.display());
+        }
+        [".venv", "venv"].into_iter()
+            .map(|name| base_dir.join(name))
+            .find(|path| path.join("pyvenv.cfg").exists())
+            .map(|path| Self { path, source: VenvSource::Directory })
+    }
+
+    /// The Python version the virtualenv was created with, as `major.minor`
+    pub fn version(&self) -> Option<String> {
+        let cfg = read_to_string(self.path.join("pyvenv.cfg")).ok()?;
+        cfg.lines()
+            .filter_map(|line| line.split_once('='))
+            .find(|(key, _)| ["version", "version_info"].contains(&key.trim()))
+            .map(|(_, version)| version.trim().split('.').take(2).collect::<Vec<_>>().join("."))
+    }
+
+    /// Whether the interpreter's own site-packages are importable too, which like Python defaults to true if it isn't set
+    pub fn include_system_site_packages(&self) -> bool {
+        let Ok(cfg) = read_to_string(self.path.join("pyvenv.cfg")) else {
+            return true;
+        };
+        cfg.lines()
+            .filter_map(|line| line.split_once('='))
+            .find(|(key, _)| key.trim() == "include-system-site-packages")
+            .is_none_or(|(_, value)| value.trim().eq_ignore_ascii_case("true"))
+    }
+
+    pub fn site_packages(&self) -> Result<Vec<PathBuf>> {
+        let pattern = if cfg!(windows) {
+            self.path.join("Lib").join("site-packages")
+        } else {
+            self.path.join("lib").join("python*").join("site-packages")
+        };
+        Ok(glob(&pattern.display().to_string())?.collect::<Result<_, _>>()?)
+    }
+
+    /// Files that decide what runs as soon as the virtualenv is activated, as `.pth` files can run code
+    pub fn startup_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = vec![self.path.join("pyvenv.cfg")];
+        for site_packages in self.site_packages()? {
+            let pattern = site_packages.join("*.pth");
+            files.extend(glob(&pattern.display().to_string())?.collect::<Result<Vec<_>, _>>()?);
+        }
+        Ok(files)
+    }
+}
+
+/// Extra directories to put on `sys.path` so solutions can import local packages
+pub fn project_paths(base_dir: &Path) -> Vec<PathBuf> {
+    if !base_dir.join("pyproject.toml").exists() {
+        return Vec::new();
+    }
+    let mut paths = vec![base_dir.to_path_buf()];
+    if base_dir.join("src").is_dir() {
+        paths.push(base_dir.join("src"));
+    }
+    paths
+}
+
+/// Points the interpreter at the virtualenv and project in `base_dir`
+///
+/// In strict mode, the virtualenv is only activated once its startup files are in `approvals`.
+pub fn activate(py: Python, base_dir: &Path, approvals: Option<&mut Approvals>) -> Result<Option<VirtualEnv>> {
+    let sys = py.import("sys")?;
+    let path: &PyList = sys.getattr("path")?.downcast().map_err(pyo3::PyErr::from)?;
+    for dir in project_paths(base_dir).into_iter().rev() {
+        debug!("Adding {} to sys.path", dir.display());
+        path.insert(0, dir.display().to_string())?;
+    }
+    let Some(venv) = VirtualEnv::find(base_dir) else {
+        return Ok(None);
+    };
+    if let Some(approvals) = approvals {
+        let files = venv.startup_files()?;
+        if !approve(approvals, files.iter().map(PathBuf::as_path))?.is_empty() {
+            warn!("Not using the virtualenv at {}, as its startup files weren't approved", venv.path.display());
+            return Ok(None);
+        }
+    }
+    let version_info = sys.getattr("version_info")?;
+    let interpreter_version = format!(
+        "{}.{}",
+        version_info.getattr("major")?.extract::<u8>()?,
+        version_info.getattr("minor")?.extract::<u8>()?,
+    );
+    if let Some(version) = venv.version() {
+        if version != interpreter_version {
+            warn!(
+                "Virtualenv at {} was created with Python {version}, but the embedded interpreter is Python {interpreter_version}, compiled packages may fail to import",
+                venv.path.display(),
+            );
+        }
+    }
+    let site = py.import("site")?;
+    if !venv.include_system_site_packages() {
+        // These depend on `sys.prefix`, so are found before it's changed
+        let mut system: Vec<String> = site.call_method0("getsitepackages")?.extract()?;
+        system.push(site.call_method0("getusersitepackages")?.extract()?);
+        for dir in system {
+            while let Ok(index) = path.index(&dir) {
+                debug!("Removing system site directory {dir} from sys.path");
+                path.del_item(index)?;
+            }
+        }
+    }
+    let prefix = venv.path.display().to_string();
+    sys.setattr("prefix", &prefix)?;
+    sys.setattr("exec_prefix", &prefix)?;
+    let before: Vec<String> = path.extract()?;
+    for site_packages in venv.site_packages()? {
+        debug!("Adding site directory {}", site_packages.display());
+        site.call_method1("addsitedir", (site_packages.display().to_string(),))?;
+    }
+    // `addsitedir` appends, which would let the interpreter's packages shadow the virtualenv's,
+    // so the new entries, including any added by `.pth` files, are moved to just after the project's
+    let added: Vec<String> = path.extract::<Vec<String>>()?.into_iter()
+        .filter(|entry| !before.contains(entry))
+        .collect();
+    let front = project_paths(base_dir).len();
+    for (offset, entry) in added.iter().enumerate() {
+        path.del_item(path.index(entry)?)?;
+        path.insert(front + offset, entry)?;
+    }
+    Ok(Some(venv))
+}
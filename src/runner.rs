@@ -0,0 +1,251 @@
+//! Runners load and run solutions written in a particular language
+//!
+//! Each runner is given every file under the current directory outside hidden, build and environment directories,
+//! and picks out the ones it handles, usually by their extension,
+//! then registers the solutions in them with [`register`](crate::solutions::register).
+//! How a solution is run is up to the [`Implementation`](crate::solutions::Implementation) its runner registers.
+
+use std::collections::BTreeSet;
+use std::env::current_dir;
+use std::ffi::OsStr;
+use std::fs::read_dir;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Output};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use anyhow::{bail, Result};
+use indicatif::ProgressIterator;
+use tracing::error;
+use crate::display::{ActionType, println, progress_bar};
+use crate::external::ExternalRunner;
+use crate::integrity::{approve, Approvals};
+use crate::native::NativeRunner;
+use crate::project::Settings;
+use crate::python::runner::PythonRunner;
+use crate::wasm::WasmRunner;
+
+pub trait Runner {
+    /// The name of the language, e.g. `Python`
+    fn name(&self) -> &'static str;
+
+    /// Whether this runner loads solutions from `file`
+    fn handles(&self, file: &Path) -> bool;
+
+    /// Finds the files to load solutions from, given every file under the current directory
+    fn discover(&self, files: &[PathBuf]) -> Vec<PathBuf> {
+        files.iter()
+            .filter(|file| self.handles(file))
+            .cloned()
+            .collect()
+    }
+
+    /// Finds every file that loading or running `file` can execute, which strict mode checks before loading it,
+    /// or an error if they can't be known, in which case strict mode doesn't load it
+    fn sources(&self, file: &Path) -> Result<Vec<PathBuf>> {
+        Ok(vec![file.to_path_buf()])
+    }
+
+    /// Registers the solutions in `file`, returning the number that failed to register,
+    /// or an error if the whole file failed to load
+    fn load(&self, file: &Path) -> Result<u16>;
+
+    /// Prints information about the runner's environment, for `aoc doctor`
+    fn describe(&self) -> Result<()>;
+}
+
+/// Whether `file` has one of `extensions`
+pub fn has_extension(file: &Path, extensions: &[&str]) -> bool {
+    file.extension().is_some_and(|extension| extensions.iter().any(|expected| extension == *expected))
+}
+
+/// Sets up every runner enabled in `settings`, in the order their files are loaded
+///
+/// In strict mode, anything a runner would run while being set up must be in `approvals` first.
+pub fn runners(base_dir: &Path, settings: &Settings, approvals: Option<&mut Approvals>) -> Result<Vec<Box<dyn Runner>>> {
+    const NAMES: &[&str] = &["Python", "Rust", "Commands", "WebAssembly"];
+    if let Some(unknown) = settings.runners.iter().flatten().find(|name| !NAMES.iter().any(|known| known.eq_ignore_ascii_case(name))) {
+        bail!("Unknown runner: {unknown}, expected one of {}", NAMES.join(", "));
+    }
+    let mut runners: Vec<Box<dyn Runner>> = Vec::new();
+    // Only started when enabled, as starting the interpreter fails without Python installed
+    if settings.runner_enabled("Python") {
+        runners.push(Box::new(PythonRunner::new(base_dir, approvals)?));
+    }
+    if settings.runner_enabled("Rust") {
+        runners.push(Box::new(NativeRunner));
+    }
+    if settings.runner_enabled("Commands") {
+        runners.push(Box::new(ExternalRunner::new(base_dir)));
+    }
+    if settings.runner_enabled("WebAssembly") {
+        runners.push(Box::new(WasmRunner::new()));
+    }
+    Ok(runners)
+}
+
+/// Whether `file`, relative to the current directory, is in one of `roots`, if there are any
+pub fn in_roots(roots: Option<&[PathBuf]>, file: &Path) -> bool {
+    roots.is_none_or(|roots| current_dir().is_ok_and(|cwd| roots.iter().any(|root| cwd.join(file).starts_with(root))))
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut output).ok();
+        }
+        output
+    })
+}
+
+/// Waits for a process to exit and collects its output, killing it if it runs for longer than `timeout`
+///
+/// Returns `None` if it was killed.
+pub fn wait_with_timeout(mut child: Child, timeout: Option<Duration>) -> Result<Option<Output>> {
+    /// How often the process is checked for having exited
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    let Some(timeout) = timeout else {
+        return Ok(Some(child.wait_with_output()?));
+    };
+    // Read as the process runs, so it doesn't block on a full pipe
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() > timeout {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    Ok(Some(Output {
+        status,
+        stdout: stdout.join().unwrap(),
+        stderr: stderr.join().unwrap(),
+    }))
+}
+
+/// The runner that loads solutions from `file`
+pub fn runner_for<'a>(runners: &'a [Box<dyn Runner>], file: &Path) -> Option<&'a dyn Runner> {
+    runners.iter()
+        .find(|runner| runner.handles(file))
+        .map(AsRef::as_ref)
+}
+
+/// Directories holding build output or installed packages rather than solutions, which are never searched
+const SKIPPED_DIRS: &[&str] = &["target", "venv", "node_modules", "__pycache__"];
+
+fn is_skipped(name: &OsStr) -> bool {
+    name.to_string_lossy().starts_with('.') || SKIPPED_DIRS.iter().any(|skipped| name == *skipped)
+}
+
+/// Whether `file`, relative to the current directory, is in a hidden, build or environment directory
+pub fn in_skipped_dir(file: &Path) -> bool {
+    file.parent().is_some_and(|dir| dir.components().any(|component| matches!(component, Component::Normal(name) if is_skipped(name))))
+}
+
+/// Finds every file under the current directory, skipping hidden, build and environment directories
+fn files() -> Result<Vec<PathBuf>> {
+    files_in(Path::new("."))
+}
+
+/// Finds every file under `dir`, skipping hidden files and hidden, build and environment directories
+pub fn files_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // Relative to the current directory without a leading `./`, like the paths in events from the watcher
+            let path: PathBuf = dir.join(&name).components().filter(|component| *component != Component::CurDir).collect();
+            if entry.file_type()?.is_dir() {
+                if !is_skipped(&name) {
+                    dirs.push(path);
+                }
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Loads the solutions from every file a runner handles, returning the number of files and solutions that failed to load
+///
+/// Only files in `roots` are loaded if there are any, and in strict mode, only once every file they run is in `approvals`.
+pub fn load_solutions(runners: &[Box<dyn Runner>], roots: Option<&[PathBuf]>, approvals: Option<&mut Approvals>) -> Result<u16> {
+    let files: Vec<_> = files()?.into_iter()
+        .filter(|file| in_roots(roots, file))
+        .collect();
+    let mut files: Vec<_> = runners.iter()
+        .flat_map(|runner| runner.discover(&files).into_iter().map(move |file| (runner, file)))
+        .collect();
+    if let Some(approvals) = approvals {
+        let mut sources = Vec::new();
+        files.retain(|(runner, file)| match runner.sources(file) {
+            Ok(found) => {
+                sources.push(found);
+                true
+            }
+            Err(error) => {
+                println("Skipped", ActionType::Warning, format!("{}: {error}", file.display()));
+                false
+            }
+        });
+        let rejected = approve(approvals, sources.iter().flatten().collect::<BTreeSet<_>>().into_iter().map(PathBuf::as_path))?;
+        let mut sources = sources.into_iter();
+        files.retain(|(_, file)| {
+            let approved = !sources.next().unwrap().iter().any(|source| rejected.contains(source));
+            if !approved {
+                println("Skipped", ActionType::Warning, format!("{}, it or files it runs weren't approved", file.display()));
+            }
+            approved
+        });
+    }
+    let mut failures = 0u16;
+    let bar = progress_bar("Importing".to_string(), ActionType::Prepare, files.len() as u64)?;
+    for (runner, file) in files.into_iter().progress_with(bar.clone()) {
+        bar.set_message(file.display().to_string());
+        match runner.load(&file) {
+            Ok(count) => failures += count,
+            Err(error) => {
+                error!("Failed to import {}\n\n{error}", file.display());
+                failures += 1;
+            }
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+    use super::*;
+
+    #[test]
+    fn test_files_in() {
+        let dir = temp_dir().join(format!("aoc-files-{}", std::process::id()));
+        for file in ["a.py", "day01/b.py", ".hidden/c.py", "target/d.wasm", "venv/e.py", "day01/__pycache__/b.pyc"] {
+            let file = dir.join(file);
+            create_dir_all(file.parent().unwrap()).unwrap();
+            write(file, "").unwrap();
+        }
+        let files = files_in(&dir).unwrap();
+        remove_dir_all(&dir).unwrap();
+        assert_eq!(files, vec![dir.join("a.py"), dir.join("day01/b.py")]);
+        assert!(in_skipped_dir(Path::new("target/release/x.wasm")));
+        assert!(!in_skipped_dir(Path::new("day01/target.py")));
+    }
+}
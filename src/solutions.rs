@@ -0,0 +1,210 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::env::current_dir;
+use std::fmt::{Debug, Display, Formatter};
+use std::fs::canonicalize;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use anyhow::Result;
+use lazy_static::lazy_static;
+
+/// Where a solution was defined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub(crate) file: PathBuf,
+    /// `None` for files without lines, e.g. WebAssembly modules
+    pub(crate) line: Option<u32>,
+}
+
+impl Display for Location {
+    /// Formats as `file:line`, relative to the current directory if possible, which most terminals and editors can jump to
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let cwd = current_dir().ok();
+        let file = cwd.as_ref()
+            .and_then(|cwd| self.file.strip_prefix(cwd).ok())
+            .unwrap_or(&self.file);
+        match self.line {
+            Some(line) => write!(f, "{}:{line}", file.display()),
+            None => write!(f, "{}", file.display()),
+        }
+    }
+}
+
+/// The answer to each part a solution solved, or why it failed, and anything it printed
+pub type Outcome = (Result<Vec<(u8, String)>, Failure>, String);
+
+/// Why a solution failed to produce an answer
+pub struct Failure {
+    pub(crate) message: String,
+    /// Interactively debugs the failure, for runners that support it
+    pub(crate) debugger: Option<Box<dyn FnOnce() -> Result<()>>>,
+}
+
+impl Failure {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            debugger: None,
+        }
+    }
+
+    pub fn timed_out(timeout: Duration) -> Self {
+        Self::new(format!("Timed out after {}s", timeout.as_secs_f64()))
+    }
+
+    pub fn with_debugger(self, debugger: impl FnOnce() -> Result<()> + 'static) -> Self {
+        Self {
+            debugger: Some(Box::new(debugger)),
+            ..self
+        }
+    }
+}
+
+/// How a runner runs a solution it loaded
+pub trait Implementation: Debug + Send + Sync {
+    /// Runs the solution with `input`, failing if it takes longer than `timeout`
+    fn run(&self, input: &str, timeout: Option<Duration>) -> Result<Outcome>;
+
+    /// Whether `other` is the same function, for implementations that can tell without a location
+    fn is(&self, _other: &dyn Implementation) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub(crate) year: u16,
+    pub(crate) day: u8,
+    /// The part this solves, or `None` if it solves both
+    pub(crate) part: Option<u8>,
+    /// The qualified name of the function
+    pub(crate) name: String,
+    /// Where the function was defined, or `None` if it isn't known (e.g. Python builtins)
+    pub(crate) location: Option<Location>,
+    pub(crate) implementation: Arc<dyn Implementation>,
+}
+
+impl Solution {
+    pub fn parts(&self) -> &'static [u8] {
+        match self.part {
+            Some(1) => &[1],
+            Some(2) => &[2],
+            _ => &[1, 2],
+        }
+    }
+
+    /// The name of the solution, with where it was defined if known
+    pub fn description(&self) -> String {
+        match &self.location {
+            Some(location) => format!("{} at {location}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Whether `other` is the same function, e.g. because its file was loaded again
+    fn is(&self, other: &Solution) -> bool {
+        self.location.is_some() && self.location == other.location
+            || self.implementation.is(other.implementation.as_ref())
+    }
+
+    /// Runs the solution, returning the answer to each part it solved, or why it failed, and anything it printed
+    pub fn run(&self, input: &str, timeout: Option<Duration>) -> Result<Outcome> {
+        self.implementation.run(input, timeout)
+    }
+}
+
+lazy_static! {
+    /// Registered solutions, keyed by the file they were defined in so a file's solutions can be replaced when it changes
+    static ref SOLUTIONS: RwLock<BTreeMap<Option<PathBuf>, Vec<Solution>>> = RwLock::new(BTreeMap::new());
+}
+
+/// Every registered solution, grouped by the file they were defined in
+pub fn solutions() -> Vec<Solution> {
+    SOLUTIONS.read().unwrap().values().flatten().cloned().collect()
+}
+
+/// Checks that a solution is for a puzzle that exists
+pub fn validate(year: u16, day: u8, part: Option<u8>) -> Result<(), String> {
+    if year < 2015 {
+        return Err(format!("Invalid year: {year}, Advent of Code started in 2015"));
+    }
+    if !(1..=25).contains(&day) {
+        return Err(format!("Invalid day: {day}, expected 1 to 25"));
+    }
+    if let Some(part) = part.filter(|part| ![1, 2].contains(part)) {
+        return Err(format!("Invalid part number: {part}, expected 1 or 2"));
+    }
+    Ok(())
+}
+
+/// Registers a solution, unless the same function is already registered
+///
+/// Fails if a part it solves already has a solution and `alternative` isn't set.
+pub fn register(solution: Solution, alternative: bool) -> Result<(), String> {
+    let mut solutions = SOLUTIONS.write().unwrap();
+    let existing: Vec<_> = solutions.values()
+        .flatten()
+        .filter(|existing| (existing.year, existing.day) == (solution.year, solution.day))
+        .filter(|existing| existing.parts().iter().any(|part| solution.parts().contains(part)))
+        .collect();
+    if existing.iter().any(|existing| existing.is(&solution)) {
+        return Ok(());
+    }
+    if let (Some(existing), false) = (existing.first(), alternative) {
+        let description = match solution.part {
+            Some(part) => format!("{} day {} part {part}", solution.year, solution.day),
+            None => format!("{} day {}", solution.year, solution.day),
+        };
+        return Err(format!("{description} already has a solution ({})", existing.description()));
+    }
+    solutions.entry(solution.location.as_ref().map(|location| location.file.clone())).or_default().push(solution);
+    Ok(())
+}
+
+/// Removes every solution defined in `file`, so it can be loaded again, returning how many were removed
+pub fn unregister_file(file: &Path) -> usize {
+    SOLUTIONS.write().unwrap().remove(&Some(canonical(file))).map_or(0, |solutions| solutions.len())
+}
+
+/// The canonical path solutions in `file` were registered under, even once it's been deleted,
+/// by canonicalizing the closest ancestor that still exists
+fn canonical(file: &Path) -> PathBuf {
+    let Some((mut canonical, rest)) = file.ancestors()
+        .find_map(|ancestor| Some((canonicalize(ancestor).ok()?, file.strip_prefix(ancestor).ok()?))) else {
+        return file.to_path_buf();
+    };
+    // What's left doesn't exist, so it can't contain symlinks
+    for component in rest.components() {
+        match component {
+            Component::ParentDir => {
+                canonical.pop();
+            }
+            Component::CurDir => {}
+            component => canonical.push(component),
+        }
+    }
+    canonical
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+    use super::*;
+
+    #[test]
+    fn test_canonical_deleted_file() {
+        let dir = temp_dir().join(format!("aoc-solutions-{}", std::process::id()));
+        create_dir_all(dir.join("2023")).unwrap();
+        let file = dir.join("2023/../2023/day01.py");
+        write(&file, "").unwrap();
+        let registered = canonical(&file);
+        remove_dir_all(dir.join("2023")).unwrap();
+        let deleted = canonical(&file);
+        remove_dir_all(&dir).unwrap();
+        assert_eq!(deleted, registered);
+    }
+}
@@ -0,0 +1,487 @@
+//! Templates for `aoc new`
+//!
+//! A template is either a single file, `<name>.tera`, or a directory of files rendered together.
+//! Paths of files in a directory are templates too, so they can depend on the puzzle,
+//! and only files ending in `.tera` have their contents rendered.
+//! Templates are found in the project's templates directory, then a shared directory, then those built in.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs::{read_to_string, DirEntry};
+use std::path::{Component, Path, PathBuf};
+use anyhow::{bail, Context as _, Result};
+use glob::glob;
+use serde::Serialize;
+use tera::{Context, Tera, Value};
+use tracing::warn;
+use crate::Config;
+use crate::project::Settings;
+
+/// Where a single file template is created when no path is given, relative to the trusted directory
+pub const DEFAULT_PATH: &str = "{{ year }}/day{{ day | pad }}.{{ template }}";
+
+/// Pads a number with zeros to `width` digits, 2 by default
+fn pad(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let width = match args.get("width") {
+        Some(width) => usize::try_from(width.as_u64().ok_or("`width` must be a positive number")?)
+            .map_err(|_| "`width` is too large")?,
+        None => 2,
+    };
+    let number = match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(string) => string.parse().ok(),
+        _ => None,
+    };
+    // Placeholders like `...` for a missing day are left as they are
+    Ok(number.map_or_else(|| value.clone(), |number| Value::String(format!("{number:0width$}"))))
+}
+
+/// How many lines of the input templates can see
+const PREVIEW_LINES: usize = 5;
+
+/// What a puzzle's input looks like, so templates can generate code to parse it
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct InputShape {
+    /// The first few lines
+    pub preview: Vec<String>,
+    pub lines: usize,
+    /// Set if every line is the same length, with no spaces
+    pub grid: Option<Grid>,
+    /// Whether every line is only integers, separated by spaces or commas
+    pub ints: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl InputShape {
+    pub fn detect(input: &str) -> Self {
+        let lines: Vec<_> = input.lines().collect();
+        let width = lines.first().map_or(0, |line| line.len());
+        let grid = (lines.len() > 1 && width > 1 && lines.iter().all(|line| line.len() == width && !line.contains(' ')))
+            .then_some(Grid {
+                width,
+                height: lines.len(),
+            });
+        let ints = !lines.is_empty() && lines.iter().all(|line| line
+            .split([' ', ','])
+            .filter(|int| !int.is_empty())
+            .all(|int| int.parse::<i64>().is_ok()));
+        Self {
+            preview: lines.iter().take(PREVIEW_LINES).map(ToString::to_string).collect(),
+            lines: lines.len(),
+            grid,
+            ints,
+        }
+    }
+}
+
+/// Names a day like `day05`, which can be used as an identifier
+fn day_name(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    match pad(value, args)? {
+        Value::String(day) => Ok(Value::String(format!("day{day}"))),
+        _ => Err(format!("Expected a day, got {value}").into()),
+    }
+}
+
+/// Gets a number argument of a function
+fn number_arg<T: TryFrom<u64>>(args: &HashMap<String, Value>, name: &str) -> tera::Result<Option<T>> {
+    args.get(name)
+        .map(|value| value.as_u64()
+            .and_then(|value| T::try_from(value).ok())
+            .ok_or_else(|| format!("Invalid `{name}`: {value}").into()))
+        .transpose()
+}
+
+/// Loads every template from the directories they're found in, so they can include and extend each other
+///
+/// Templates are named by their path within their directory, e.g. `base.tera` or `full/notes.md.tera`,
+/// and project templates replace shared ones, which replace built-in ones.
+/// Templates that fail to parse are skipped with a warning, along with any that extend or import them.
+pub fn environment(base_dir: &Path, settings: &Settings, config: &Config) -> Result<Tera> {
+    let mut tera = Tera::default();
+    tera.register_filter("pad", pad);
+    tera.register_filter("day_name", day_name);
+    let inputs: HashMap<(u16, u8), String> = config.days.iter()
+        .flat_map(|(year, days)| days.iter().filter_map(|(day, data)| Some(((*year, *day), data.input.clone()?))))
+        .collect();
+    // The first lines of the cached input for a day, or none if it hasn't been fetched
+    tera.register_function("input_preview", move |args: &HashMap<String, Value>| {
+        let year = number_arg(args, "year")?.ok_or("`year` is required")?;
+        let day = number_arg(args, "day")?.ok_or("`day` is required")?;
+        let lines = number_arg(args, "lines")?.unwrap_or(PREVIEW_LINES);
+        let preview: Vec<_> = inputs.get(&(year, day)).into_iter()
+            .flat_map(|input| input.lines().take(lines))
+            .collect();
+        Ok(preview.into())
+    });
+    let mut templates = Vec::new();
+    for builtin in BUILTINS {
+        for (path, source) in builtin.files {
+            match path {
+                None => templates.push((format!("{}.tera", builtin.name), (*source).to_string())),
+                Some(path) if Path::new(path).extension().is_some_and(|extension| extension == "tera") => templates.push((format!("{}/{path}", builtin.name), (*source).to_string())),
+                Some(_) => {}
+            }
+        }
+    }
+    for (_, dir) in dirs(base_dir, settings).into_iter().rev() {
+        for path in glob(&dir.join("**").join("*.tera").to_string_lossy())? {
+            let path = path?;
+            if !path.is_file() {
+                continue;
+            }
+            let name = path.strip_prefix(&dir)?.components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            templates.push((name, read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?));
+        }
+    }
+    // Parsed one at a time first, so a broken template doesn't stop the others loading
+    let mut parsed = HashMap::new();
+    for (name, source) in templates {
+        match tera::Template::new(&name, None, &source) {
+            Ok(template) => {
+                parsed.insert(name, (source, template));
+            }
+            Err(error) => {
+                warn!("Skipped the template {name}, it failed to parse\n\n{error}");
+                parsed.remove(&name);
+            }
+        }
+    }
+    loop {
+        let broken: Vec<_> = parsed.iter()
+            .filter(|(_, (_, template))| template.parent.iter()
+                .chain(template.imported_macro_files.iter().map(|(file, _)| file))
+                .any(|dependency| !parsed.contains_key(dependency)))
+            .map(|(name, _)| name.clone())
+            .collect();
+        if broken.is_empty() {
+            break;
+        }
+        for name in broken {
+            warn!("Skipped the template {name}, it extends or imports a template that's missing or broken");
+            parsed.remove(&name);
+        }
+    }
+    tera.add_raw_templates(parsed.into_iter().map(|(name, (source, _))| (name, source))).context("Failed to load templates")?;
+    Ok(tera)
+}
+
+/// A file that a template renders
+#[derive(Debug)]
+pub struct Rendered {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// A file in a template
+#[derive(Debug)]
+pub struct File {
+    /// The path within a template directory, or `None` for a single file template
+    pub path: Option<String>,
+    pub source: String,
+}
+
+/// A template embedded in the binary
+#[derive(Debug)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Files as in a template directory, or a single file with no path
+    files: &'static [(Option<&'static str>, &'static str)],
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "py",
+        description: "A Python solution",
+        files: &[(None, include_str!("templates/py.tera"))],
+    },
+    Builtin {
+        name: "py-examples",
+        description: "A Python solution with tests for the puzzle's examples",
+        files: &[(Some("{{ year }}/day{{ day | pad }}.py.tera"), include_str!("templates/py-examples.tera"))],
+    },
+    Builtin {
+        name: "command",
+        description: "A shell script run as a command",
+        files: &[(Some("{{ year }}/day{{ day | pad }}.sh.tera"), include_str!("templates/command.sh.tera"))],
+    },
+];
+
+/// Where a template was found
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// The project's templates directory
+    Project,
+    /// The shared templates directory
+    Shared,
+    Builtin,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Project => write!(f, "Project"),
+            Self::Shared => write!(f, "Shared"),
+            Self::Builtin => write!(f, "Built-in"),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Kind {
+    File(PathBuf),
+    Directory(PathBuf),
+    Builtin(&'static Builtin),
+}
+
+#[derive(Debug)]
+pub struct Template {
+    pub name: String,
+    pub origin: Origin,
+    kind: Kind,
+}
+
+impl Template {
+    /// Finds the template called `name` in the project's templates, then the shared templates, then the built-in ones
+    pub fn find(base_dir: &Path, settings: &Settings, name: &str) -> Result<Self> {
+        for (origin, dir) in dirs(base_dir, settings) {
+            let directory = dir.join(name);
+            if directory.is_dir() {
+                return Ok(Self { name: name.to_string(), origin, kind: Kind::Directory(directory) });
+            }
+            let file = dir.join(format!("{name}.tera"));
+            if file.is_file() {
+                return Ok(Self { name: name.to_string(), origin, kind: Kind::File(file) });
+            }
+        }
+        match BUILTINS.iter().find(|builtin| builtin.name == name) {
+            Some(builtin) => Ok(Self { name: name.to_string(), origin: Origin::Builtin, kind: Kind::Builtin(builtin) }),
+            None => bail!("No template called `{name}`. Use `aoc template list` to see the available templates."),
+        }
+    }
+
+    /// Every available template, without those hidden by a template with the same name found first
+    pub fn all(base_dir: &Path, settings: &Settings) -> Result<Vec<Self>> {
+        let mut templates: Vec<Self> = Vec::new();
+        for (origin, dir) in dirs(base_dir, settings) {
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut entries = std::fs::read_dir(&dir)?.collect::<Result<Vec<_>, _>>()?;
+            entries.sort_by_key(DirEntry::file_name);
+            for path in entries.into_iter().map(|entry| entry.path()) {
+                let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                let (name, kind) = if path.is_dir() {
+                    (name, Kind::Directory(path.clone()))
+                } else if let Some(name) = name.strip_suffix(".tera") {
+                    (name, Kind::File(path.clone()))
+                } else {
+                    continue;
+                };
+                if !templates.iter().any(|template| template.name == name) {
+                    templates.push(Self { name: name.to_string(), origin, kind });
+                }
+            }
+        }
+        for builtin in BUILTINS {
+            if !templates.iter().any(|template| template.name == builtin.name) {
+                templates.push(Self { name: builtin.name.to_string(), origin: Origin::Builtin, kind: Kind::Builtin(builtin) });
+            }
+        }
+        Ok(templates)
+    }
+
+    /// The builtin's description, or a single file template's leading comment, e.g. `{# A Python solution #}`
+    pub fn description(&self) -> Option<String> {
+        match &self.kind {
+            Kind::Builtin(builtin) => Some(builtin.description.to_string()),
+            Kind::File(path) => leading_comment(&read_to_string(path).ok()?),
+            Kind::Directory(_) => None,
+        }
+    }
+
+    /// Where the template was found, unless it's built in
+    pub fn path(&self) -> Option<&Path> {
+        match &self.kind {
+            Kind::File(path) | Kind::Directory(path) => Some(path),
+            Kind::Builtin(_) => None,
+        }
+    }
+
+    /// The template's files, whose contents are only rendered if they're a single file or their path ends in `.tera`
+    pub fn files(&self) -> Result<Vec<File>> {
+        match &self.kind {
+            Kind::File(template) => Ok(vec![File {
+                path: None,
+                source: read_to_string(template).with_context(|| format!("Failed to read {}", template.display()))?,
+            }]),
+            Kind::Directory(template) => {
+                let pattern = template.join("**").join("*");
+                let mut files = Vec::new();
+                for path in glob(&pattern.to_string_lossy())? {
+                    let path = path?;
+                    if !path.is_file() {
+                        continue;
+                    }
+                    files.push(File {
+                        path: Some(path.strip_prefix(template)?.to_string_lossy().into_owned()),
+                        source: read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?,
+                    });
+                }
+                if files.is_empty() {
+                    bail!("{} is empty", template.display());
+                }
+                Ok(files)
+            }
+            Kind::Builtin(builtin) => Ok(builtin.files.iter()
+                .map(|(path, source)| File {
+                    path: path.map(ToString::to_string),
+                    source: (*source).to_string(),
+                })
+                .collect()),
+        }
+    }
+
+    /// Renders the template's files from `tera`, a single file to `file` and a directory's files within `dir`
+    pub fn render(&self, tera: &mut Tera, context: &Context, file: &Path, dir: &Path) -> Result<Vec<Rendered>> {
+        self.files()?.into_iter().map(|File { path, source }| {
+            let Some(path) = path else {
+                return Ok(Rendered {
+                    path: file.to_path_buf(),
+                    contents: tera.render(&format!("{}.tera", self.name), context)
+                        .with_context(|| format!("Failed to render the `{}` template", self.name))?,
+                });
+            };
+            let name = format!("{}/{}", self.name, path.replace('\\', "/"));
+            let (path, contents) = match path.strip_suffix(".tera") {
+                Some(path) => (path, tera.render(&name, context)
+                    .with_context(|| format!("Failed to render {path} in the `{}` template", self.name))?),
+                None => (&*path, source),
+            };
+            Ok(Rendered {
+                path: dir.join(render_path(tera, path, context)?),
+                contents,
+            })
+        }).collect()
+    }
+}
+
+/// The text of a comment at the start of a template, on one line
+fn leading_comment(source: &str) -> Option<String> {
+    let comment = source.trim_start().strip_prefix("{#")?;
+    let comment = comment[..comment.find("#}")?].trim_start_matches('-').trim_end_matches('-');
+    let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!comment.is_empty()).then_some(comment)
+}
+
+/// The directories templates are found in, in the order they're searched
+fn dirs(base_dir: &Path, settings: &Settings) -> Vec<(Origin, PathBuf)> {
+    let mut dirs = vec![(Origin::Project, settings.templates(base_dir))];
+    if let Some(shared) = &settings.shared_templates {
+        dirs.push((Origin::Shared, shared.clone()));
+    }
+    dirs
+}
+
+/// Renders a templated path, which must stay within the directory it's relative to
+pub fn render_path(tera: &mut Tera, pattern: &str, context: &Context) -> Result<PathBuf> {
+    let path = PathBuf::from(tera.render_str(pattern, context)
+        .with_context(|| format!("Failed to render the path `{pattern}`"))?);
+    if !path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir)) {
+        bail!("`{pattern}` rendered to {}, which isn't a relative path within the directory", path.display());
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+    use super::*;
+
+    #[test]
+    fn test_render_path() {
+        let mut tera = environment(Path::new("templates"), &Settings::default(), &Config::default()).unwrap();
+        let mut context = Context::new();
+        context.insert("year", "2023");
+        context.insert("day", &5);
+        context.insert("template", "py");
+        assert_eq!(render_path(&mut tera, DEFAULT_PATH, &context).unwrap(), Path::new("2023/day05.py"));
+        assert_eq!(render_path(&mut tera, "{{ day | pad(width=3) }}", &context).unwrap(), Path::new("005"));
+        assert!(render_path(&mut tera, "../{{ day }}", &context).is_err());
+        context.insert("day", "...");
+        assert_eq!(render_path(&mut tera, "day{{ day | pad }}", &context).unwrap(), Path::new("day..."));
+        context.insert("day", &7);
+        assert_eq!(render_path(&mut tera, "{{ day | day_name }}", &context).unwrap(), Path::new("day07"));
+    }
+
+    #[test]
+    fn test_builtins() {
+        let mut tera = environment(Path::new("templates"), &Settings::default(), &Config::default()).unwrap();
+        let mut context = Context::new();
+        context.insert("year", "2023");
+        context.insert("day", "1");
+        context.insert("part", "...");
+        context.insert("url", "https://adventofcode.com/2023/day/1");
+        context.insert("template", "test");
+        let with_puzzle = {
+            let mut context = context.clone();
+            context.insert("title", "Trebuchet?!");
+            context.insert("examples", &["1abc2\n", "two1nine\n"]);
+            context.insert("answers", &[Some("142"), None]);
+            context.insert("input", &InputShape::detect("1abc2\n"));
+            context
+        };
+        for builtin in BUILTINS {
+            let template = Template { name: builtin.name.to_string(), origin: Origin::Builtin, kind: Kind::Builtin(builtin) };
+            for context in [&context, &with_puzzle] {
+                let rendered = template.render(&mut tera, context, Path::new("file"), Path::new("dir")).unwrap();
+                assert!(rendered.iter().all(|rendered| rendered.path == Path::new("file") || rendered.path.to_string_lossy().starts_with("dir/2023/day01.")));
+            }
+        }
+    }
+
+    #[test]
+    fn test_broken_templates() {
+        let base_dir = temp_dir().join(format!("aoc-templates-{}", std::process::id()));
+        create_dir_all(base_dir.join("templates")).unwrap();
+        for (name, source) in [("broken", "{{ oops"), ("child", "{% extends \"broken.tera\" %}"), ("good", "{{ year }}")] {
+            write(base_dir.join("templates").join(format!("{name}.tera")), source).unwrap();
+        }
+        let tera = environment(&base_dir, &Settings::default(), &Config::default());
+        remove_dir_all(&base_dir).unwrap();
+        let names: Vec<_> = tera.unwrap().get_template_names().map(ToString::to_string).collect();
+        assert!(names.contains(&"good.tera".to_string()) && names.contains(&"py.tera".to_string()));
+        assert!(!names.contains(&"broken.tera".to_string()) && !names.contains(&"child.tera".to_string()));
+    }
+
+    #[test]
+    fn test_leading_comment() {
+        assert_eq!(leading_comment("\n{#- A Python\n   solution -#}\nimport aoc").as_deref(), Some("A Python solution"));
+        assert_eq!(leading_comment("import aoc\n{# Not leading #}"), None);
+        assert_eq!(leading_comment("{# #}"), None);
+        assert_eq!(leading_comment("{# Unclosed"), None);
+    }
+
+    #[test]
+    fn test_input_shape() {
+        let shape = InputShape::detect("#.#\n..#\n");
+        assert_eq!(shape.grid, Some(Grid { width: 3, height: 2 }));
+        assert!(!shape.ints);
+        let shape = InputShape::detect("1 -2 3\n4,5\n\n6\n7\n8\n");
+        assert_eq!(shape.grid, None);
+        assert!(shape.ints);
+        assert_eq!(shape.lines, 6);
+        assert_eq!(shape.preview, ["1 -2 3", "4,5", "", "6", "7"]);
+    }
+}
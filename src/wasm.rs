@@ -0,0 +1,410 @@
+//! Sandboxed solutions compiled to WebAssembly
+//!
+//! Modules can't access the filesystem, network or anything else outside their own memory,
+//! so they are safe to run even from untrusted sources. They must export:
+//! - `memory`
+//! - `alloc(length: i32) -> i32`, returning where `length` bytes of input can be written
+//! - `solutions() -> i64`, listing the parts the module solves as lines of `year day part`
+//! - `solve(year: i32, day: i32, part: i32, input: i32, input_length: i32) -> i64`, returning the answer
+//!
+//! Strings are UTF-8, and returned as `pointer << 32 | length`.
+//! The only import available is `aoc.print(pointer: i32, length: i32)`, which prints a string.
+
+use std::any::Any;
+use std::fs::read;
+use std::path::{Component, Path};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, bail, Result};
+use tracing::error;
+use wasmi::{
+    Caller, Config, Engine, Extern, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TrapCode,
+    TypedResumableCall,
+};
+use crate::display::{ActionType, println};
+use crate::runner::{has_extension, Runner};
+use crate::solutions::{Failure, Implementation, Location, Outcome, register, Solution, validate};
+
+/// Fuel given to a solution at a time, roughly the number of instructions it can run before checking its timeout
+const FUEL_SLICE: u64 = 1_000_000;
+/// Most fuel a solution is given in total, so it stops even without a timeout
+#[cfg(not(test))]
+const SOLVE_FUEL: u64 = 100_000_000_000;
+/// Fuel given to the start function, `alloc` and `solutions()` each, which have no timeout to stop them
+#[cfg(not(test))]
+const SETUP_FUEL: u64 = 100_000_000;
+/// Less in tests, so running out doesn't take long without optimisations
+#[cfg(test)]
+const SOLVE_FUEL: u64 = 10_000_000;
+#[cfg(test)]
+const SETUP_FUEL: u64 = 1_000_000;
+/// Largest a module's memory can grow to, in bytes
+const MEMORY_LIMIT: usize = 1 << 30;
+/// Most a module can print, in bytes, anything after it is dropped
+const OUTPUT_LIMIT: usize = 1 << 20;
+
+/// State kept in a sandbox's store
+struct State {
+    /// Everything the module printed
+    output: String,
+    limits: StoreLimits,
+}
+
+/// A fresh instance of a module, so nothing is shared between runs
+struct Sandbox {
+    store: Store<State>,
+    instance: Instance,
+    memory: Memory,
+}
+
+impl Sandbox {
+    fn new(engine: &Engine, module: &Module) -> Result<Self> {
+        let limits = StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build();
+        let mut store = Store::new(engine, State { output: String::new(), limits });
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(SETUP_FUEL)?;
+        let mut linker = Linker::new(engine);
+        linker.func_wrap("aoc", "print", |mut caller: Caller<'_, State>, pointer: i32, length: i32| {
+            let Some(Extern::Memory(memory)) = caller.get_export("memory") else {
+                return;
+            };
+            // Only past the limit once the truncation has been noted
+            let printed = caller.data().output.len();
+            if printed > OUTPUT_LIMIT {
+                return;
+            }
+            let text = read_bytes(memory.data(&caller), pointer, length)
+                .and_then(|bytes| if bytes.len() > OUTPUT_LIMIT - printed {
+                    let shown = String::from_utf8_lossy(&bytes[..OUTPUT_LIMIT - printed]);
+                    Ok(format!("{shown}\n<output truncated after {OUTPUT_LIMIT} bytes>"))
+                } else {
+                    Ok(String::from_utf8(bytes.to_vec())?)
+                })
+                .unwrap_or_else(|error| format!("<{error}>"));
+            caller.data_mut().output.push_str(&text);
+        })?;
+        let instance = linker.instantiate_and_start(&mut store, module).map_err(|error| match error.as_trap_code() {
+            Some(TrapCode::OutOfFuel) => out_of_fuel("The start function"),
+            _ => anyhow::Error::from(error).context("Failed to instantiate the module, it can only import `aoc.print`"),
+        })?;
+        let memory = instance.get_memory(&store, "memory").ok_or_else(|| anyhow!("The module doesn't export its memory"))?;
+        Ok(Self { store, instance, memory })
+    }
+
+    /// Reads a string returned as `pointer << 32 | length`
+    #[allow(clippy::cast_possible_truncation)]
+    fn string(&self, packed: i64) -> Result<String> {
+        read_string(self.memory.data(&self.store), (packed >> 32) as i32, packed as i32)
+    }
+
+    /// Copies `bytes` into memory allocated by the module, returning their pointer and length
+    fn write(&mut self, bytes: &[u8]) -> Result<(i32, i32)> {
+        let length = i32::try_from(bytes.len())?;
+        let alloc = self.instance.get_typed_func::<i32, i32>(&self.store, "alloc")?;
+        self.store.set_fuel(SETUP_FUEL)?;
+        let pointer = alloc.call(&mut self.store, length).map_err(|error| limited(error, "`alloc`"))?;
+        self.memory.write(&mut self.store, usize::try_from(pointer)?, bytes)?;
+        Ok((pointer, length))
+    }
+}
+
+/// Converts an error from a call outside `solve`, explaining when it ran out of fuel
+fn limited(error: wasmi::Error, name: &str) -> anyhow::Error {
+    match error.as_trap_code() {
+        Some(TrapCode::OutOfFuel) => out_of_fuel(name),
+        _ => error.into(),
+    }
+}
+
+fn out_of_fuel(name: &str) -> anyhow::Error {
+    anyhow!("{name} ran out of fuel, it can run at most {SETUP_FUEL} instructions")
+}
+
+fn read_bytes(memory: &[u8], pointer: i32, length: i32) -> Result<&[u8]> {
+    let start = u32::from_ne_bytes(pointer.to_ne_bytes()) as usize;
+    let end = start + u32::from_ne_bytes(length.to_ne_bytes()) as usize;
+    memory.get(start..end).ok_or_else(|| anyhow!("String at {start}..{end} is out of bounds"))
+}
+
+fn read_string(memory: &[u8], pointer: i32, length: i32) -> Result<String> {
+    Ok(String::from_utf8(read_bytes(memory, pointer, length)?.to_vec())?)
+}
+
+#[derive(Debug)]
+struct WasmSolution {
+    engine: Engine,
+    module: Module,
+    year: u16,
+    day: u8,
+    part: u8,
+}
+
+impl Implementation for WasmSolution {
+    /// Runs the solution in a new instance of its module, failing if it traps
+    ///
+    /// The solution is given fuel a slice at a time, checking whether it has timed out whenever it runs out,
+    /// and fails once it has used [`SOLVE_FUEL`] in total.
+    fn run(&self, input: &str, timeout: Option<Duration>) -> Result<Outcome> {
+        let mut sandbox = Sandbox::new(&self.engine, &self.module)?;
+        let (pointer, length) = sandbox.write(input.as_bytes())?;
+        let solve = sandbox.instance.get_typed_func::<(i32, i32, i32, i32, i32), i64>(&sandbox.store, "solve")?;
+        let start = Instant::now();
+        let mut remaining = SOLVE_FUEL - FUEL_SLICE;
+        sandbox.store.set_fuel(FUEL_SLICE)?;
+        let mut call = solve.call_resumable(&mut sandbox.store, (self.year.into(), self.day.into(), self.part.into(), pointer, length));
+        let result = loop {
+            match call {
+                Ok(TypedResumableCall::Finished(answer)) => break Ok(answer),
+                Ok(TypedResumableCall::OutOfFuel(paused)) => match timeout {
+                    Some(timeout) if start.elapsed() > timeout => {
+                        return Ok((Err(Failure::timed_out(timeout)), sandbox.store.data().output.clone()));
+                    }
+                    _ if remaining == 0 => break Err(format!("Ran out of fuel after {SOLVE_FUEL} instructions")),
+                    _ => {
+                        let slice = FUEL_SLICE.min(remaining);
+                        remaining -= slice;
+                        sandbox.store.set_fuel(slice)?;
+                        call = paused.resume(&mut sandbox.store);
+                    }
+                },
+                Ok(TypedResumableCall::HostTrap(trap)) => break Err(trap.host_error().to_string()),
+                Err(error) => break Err(error.to_string()),
+            }
+        };
+        let output = sandbox.store.data().output.clone();
+        match result {
+            Ok(answer) => Ok((Ok(vec![(self.part, sandbox.string(answer)?)]), output)),
+            Err(error) => Ok((Err(Failure::new(error)), output)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Loads `.wasm` modules, running each solution in its own sandbox
+pub struct WasmRunner {
+    engine: Engine,
+}
+
+impl WasmRunner {
+    pub fn new() -> Self {
+        let mut config = Config::default();
+        // Used to stop solutions that run for longer than the timeout, and anything else that runs for too long
+        config.consume_fuel(true);
+        Self {
+            engine: Engine::new(&config),
+        }
+    }
+
+    /// Lists the parts a module solves as `(year, day, part)`
+    fn solutions(&self, module: &Module) -> Result<Vec<(u16, u8, u8)>> {
+        let mut sandbox = Sandbox::new(&self.engine, module)?;
+        let solutions = sandbox.instance.get_typed_func::<(), i64>(&sandbox.store, "solutions")?;
+        sandbox.store.set_fuel(SETUP_FUEL)?;
+        let solutions = solutions.call(&mut sandbox.store, ()).map_err(|error| limited(error, "`solutions()`"))?;
+        sandbox.string(solutions)?.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let [year, day, part] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                    bail!("Invalid solution: {line}, expected `year day part`");
+                };
+                Ok((year.parse()?, day.parse()?, part.parse()?))
+            })
+            .collect()
+    }
+}
+
+impl Runner for WasmRunner {
+    fn name(&self) -> &'static str {
+        "WebAssembly"
+    }
+
+    fn handles(&self, file: &Path) -> bool {
+        has_extension(file, &["wasm"])
+            && !file.components().any(|component| component == Component::Normal("target".as_ref()))
+    }
+
+    fn load(&self, file: &Path) -> Result<u16> {
+        let module = Module::new(&self.engine, read(file)?)?;
+        let mut failures = 0u16;
+        for (year, day, part) in self.solutions(&module)? {
+            let solution = Solution {
+                year,
+                day,
+                part: Some(part),
+                name: file.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                location: Some(Location {
+                    file: file.canonicalize()?,
+                    line: None,
+                }),
+                implementation: Arc::new(WasmSolution {
+                    engine: self.engine.clone(),
+                    module: module.clone(),
+                    year,
+                    day,
+                    part,
+                }),
+            };
+            if let Err(error) = validate(year, day, Some(part)).and_then(|()| register(solution, false)) {
+                error!("{}: {error}", file.display());
+                failures += 1;
+            }
+        }
+        Ok(failures)
+    }
+
+    fn describe(&self) -> Result<()> {
+        println("Sandbox", ActionType::Info, "modules have no filesystem or network access");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULE: &str = r#"
+        (module
+            (import "aoc" "print" (func $print (param i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "2023 1 1\n2023 1 2\n")
+            (data (i32.const 32) "hello")
+            (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "solutions") (result i64) (i64.const 18))
+            (func (export "solve") (param i32 i32 i32 i32 i32) (result i64)
+                (call $print (i32.const 32) (i32.const 5))
+                (if (i32.eq (local.get 2) (i32.const 2)) (then unreachable))
+                ;; The answer is the input itself
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get 3)) (i64.const 32))
+                    (i64.extend_i32_u (local.get 4))))
+        )
+    "#;
+
+    fn solution(runner: &WasmRunner, module: &Module, part: u8) -> WasmSolution {
+        WasmSolution {
+            engine: runner.engine.clone(),
+            module: module.clone(),
+            year: 2023,
+            day: 1,
+            part,
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let runner = WasmRunner::new();
+        let module = Module::new(&runner.engine, wat::parse_str(MODULE).unwrap()).unwrap();
+        assert_eq!(runner.solutions(&module).unwrap(), vec![(2023, 1, 1), (2023, 1, 2)]);
+
+        let (answers, output) = solution(&runner, &module, 1).run("input", None).unwrap();
+        assert_eq!(answers.ok().unwrap(), vec![(1, "input".to_string())]);
+        assert_eq!(output, "hello");
+
+        let (answers, output) = solution(&runner, &module, 2).run("input", None).unwrap();
+        assert!(answers.is_err());
+        assert_eq!(output, "hello");
+    }
+
+    #[test]
+    fn test_timeout() {
+        let runner = WasmRunner::new();
+        let module = r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "solve") (param i32 i32 i32 i32 i32) (result i64) (loop (br 0)) (i64.const 0))
+            )
+        "#;
+        let module = Module::new(&runner.engine, wat::parse_str(module).unwrap()).unwrap();
+        let (answers, _) = solution(&runner, &module, 1).run("", Some(Duration::from_millis(10))).unwrap();
+        assert_eq!(answers.err().unwrap().message, "Timed out after 0.01s");
+    }
+
+    #[test]
+    fn test_fuel_limit() {
+        let runner = WasmRunner::new();
+        let module = r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "solve") (param i32 i32 i32 i32 i32) (result i64) (loop (br 0)) (i64.const 0))
+            )
+        "#;
+        let module = Module::new(&runner.engine, wat::parse_str(module).unwrap()).unwrap();
+        let (answers, _) = solution(&runner, &module, 1).run("", None).unwrap();
+        assert_eq!(answers.err().unwrap().message, format!("Ran out of fuel after {SOLVE_FUEL} instructions"));
+    }
+
+    #[test]
+    fn test_output_limit() {
+        let runner = WasmRunner::new();
+        // Prints the whole first page of memory 20 times, more than the limit
+        let module = r#"
+            (module
+                (import "aoc" "print" (func $print (param i32 i32)))
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "solve") (param i32 i32 i32 i32 i32) (result i64)
+                    (local $i i32)
+                    (loop
+                        (call $print (i32.const 0) (i32.const 65536))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br_if 0 (i32.lt_u (local.get $i) (i32.const 20))))
+                    (i64.const 0))
+            )
+        "#;
+        let module = Module::new(&runner.engine, wat::parse_str(module).unwrap()).unwrap();
+        let (_, output) = solution(&runner, &module, 1).run("", None).unwrap();
+        let (printed, note) = output.split_at(OUTPUT_LIMIT);
+        assert!(printed.bytes().all(|byte| byte == 0));
+        assert_eq!(note, format!("\n<output truncated after {OUTPUT_LIMIT} bytes>"));
+    }
+
+    #[test]
+    fn test_no_other_imports() {
+        let runner = WasmRunner::new();
+        let module = r#"(module (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32))))"#;
+        let module = Module::new(&runner.engine, wat::parse_str(module).unwrap()).unwrap();
+        assert!(Sandbox::new(&runner.engine, &module).is_err());
+    }
+
+    #[test]
+    fn test_setup_fuel() {
+        let runner = WasmRunner::new();
+        let start = r#"(module (memory (export "memory") 1) (func $start (loop (br 0))) (start $start))"#;
+        let module = Module::new(&runner.engine, wat::parse_str(start).unwrap()).unwrap();
+        let error = Sandbox::new(&runner.engine, &module).err().unwrap();
+        assert!(error.to_string().starts_with("The start function ran out of fuel"));
+
+        let solutions = r#"(module (memory (export "memory") 1) (func (export "solutions") (result i64) (loop (br 0)) (i64.const 0)))"#;
+        let module = Module::new(&runner.engine, wat::parse_str(solutions).unwrap()).unwrap();
+        let error = runner.solutions(&module).unwrap_err();
+        assert!(error.to_string().starts_with("`solutions()` ran out of fuel"));
+
+        let alloc = r#"(module (memory (export "memory") 1) (func (export "alloc") (param i32) (result i32) (loop (br 0)) (i32.const 0)))"#;
+        let module = Module::new(&runner.engine, wat::parse_str(alloc).unwrap()).unwrap();
+        let error = solution(&runner, &module, 1).run("", None).err().unwrap();
+        assert!(error.to_string().starts_with("`alloc` ran out of fuel"));
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let runner = WasmRunner::new();
+        // Growing past the limit fails, returning -1, so this answers with an empty string
+        let module = r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "solve") (param i32 i32 i32 i32 i32) (result i64)
+                    (if (i32.ne (memory.grow (i32.const 16384)) (i32.const -1)) (then unreachable))
+                    (i64.const 0))
+            )
+        "#;
+        let module = Module::new(&runner.engine, wat::parse_str(module).unwrap()).unwrap();
+        let (answers, _) = solution(&runner, &module, 1).run("", None).unwrap();
+        assert_eq!(answers.ok().unwrap(), vec![(1, String::new())]);
+    }
+}